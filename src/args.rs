@@ -1,21 +1,502 @@
-pub mod args {
-    use clap::{arg, Parser};
-
-    #[derive(Parser, Debug)]
-    #[command(author, version, about, long_about = None)]
-    pub struct TJsonArgs {
-        #[arg(short, long, help = "Path to in json struct. Eg, /path/to/json/node")]
-        pub pointers: Vec<String>,
-
-        #[arg(short, long, help = "Http json resource, if not given read stdin")]
-        pub source: String,
-
-        #[arg(
-            short = 'i',
-            long = "polling-intervall",
-            default_value_t = 3,
-            help = "Polling interval in seconds"
-        )]
-        pub polling_interval: usize,
+use std::{path::PathBuf, time::Duration};
+
+use clap::{ArgGroup, ArgMatches, Parser, ValueEnum};
+use eyre::{eyre, Result};
+
+use crate::{
+    decode::InputFormat,
+    expr::{Calc, Rule},
+    format::Formatter,
+    source::Source,
+};
+
+pub const DEFAULT_POLLING_INTERVAL: u64 = 3;
+pub const DEFAULT_VIEW: &str = "main";
+pub const DEFAULT_STALE_AFTER: u64 = 60;
+pub const DEFAULT_CHECK_TIMEOUT: u64 = 60;
+pub const DEFAULT_DEBOUNCE: u64 = 5;
+
+/// Options given after a source (`--source`, `--exec`, `--sse` or
+/// `--tail`) belong to that source,
+/// options given after a `--view` are shared by the sources in that view
+/// and options given before both are shared by all sources.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+#[command(group(ArgGroup::new("input").required(true).multiple(true).args(["source", "exec", "sse", "tail"])))]
+pub struct TJsonArgs {
+    #[arg(
+        short,
+        long,
+        help = "Path to in json struct. Eg, /path/to/json/node, optionally followed by :bytes, :duration_ms, :percent, :epoch, :fixed2 or :thousands"
+    )]
+    pub pointers: Vec<String>,
+
+    #[arg(
+        short,
+        long,
+        help = "Http resource, ws:// websocket or file read every poll"
+    )]
+    pub source: Vec<String>,
+
+    #[arg(
+        short,
+        long,
+        help = "Command to run every poll, its stdout is parsed as json"
+    )]
+    pub exec: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Server-sent events resource, every event is parsed as json"
+    )]
+    pub sse: Vec<String>,
+
+    #[arg(
+        short,
+        long,
+        help = "Json lines file to follow, every appended line is parsed as json"
+    )]
+    pub tail: Vec<PathBuf>,
+
+    #[arg(long, help = "Message sent to a websocket source after connecting")]
+    pub subscribe: Vec<String>,
+
+    #[arg(short, long, help = "Name shown for the source")]
+    pub label: Vec<String>,
+
+    #[arg(
+        short = 'i',
+        long = "polling-intervall",
+        help = "Polling interval in seconds [default: 3]"
+    )]
+    pub polling_interval: Vec<u64>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Format of the source, auto guesses it [default: auto]"
+    )]
+    pub input_format: Vec<InputFormat>,
+
+    #[arg(
+        long,
+        help = "Computed column, name=expression over pointers, Eg, 'pct=/done / (/done + /left) * 100'"
+    )]
+    pub calc: Vec<Calc>,
+
+    #[arg(
+        long,
+        help = "Pointer to the time the document was produced, rfc 3339 or epoch seconds or milliseconds"
+    )]
+    pub timestamp: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Seconds after the --timestamp until the source is shown as stale [default: 60]"
+    )]
+    pub stale_after: Vec<u64>,
+
+    #[arg(
+        long = "assert",
+        value_name = "RULE",
+        help = "Rule the document should pass, Eg, '/status/state == \"SUCCESS\" && /load < 0.9'"
+    )]
+    pub rules: Vec<Rule>,
+
+    #[arg(
+        long,
+        requires = "exec_hook",
+        help = "Pointer whose changes run the --exec-hook"
+    )]
+    pub on_change: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Command run when an --on-change value changes or an --assert starts failing"
+    )]
+    pub exec_hook: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Seconds the hook waits before firing again for the same pointer or rule [default: 5]"
+    )]
+    pub debounce: Vec<u64>,
+
+    #[arg(long, help = "Number of past results shown as rows [default: 1]")]
+    pub history: Vec<usize>,
+
+    #[arg(long, help = "Start a new view, shown as a tab")]
+    pub view: Vec<String>,
+
+    #[arg(long, help = "Keep polling sources in views that are not shown")]
+    pub poll_hidden: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = SourceLayout::Stacked,
+        help = "How multiple sources are shown"
+    )]
+    pub layout: SourceLayout,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = AlertStyle::Both,
+        help = "How tjson asks for attention when an --assert starts failing"
+    )]
+    pub alert: AlertStyle,
+
+    #[arg(long, help = "Keep the first column in place when scrolling sideways")]
+    pub freeze_column: bool,
+
+    #[arg(
+        long,
+        help = "Poll until every --assert passes and exit 0, or exit 2 when one fails and 1 when it can't be told after --timeout"
+    )]
+    pub check: bool,
+
+    #[arg(
+        long,
+        default_value_t = DEFAULT_CHECK_TIMEOUT,
+        help = "Seconds --check waits for the assertions to pass"
+    )]
+    pub timeout: u64,
+
+    #[arg(
+        long,
+        help = "Config file with key bindings [default: config.toml in the user config dir]"
+    )]
+    pub config: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SourceLayout {
+    /// One table per source
+    Stacked,
+    /// One table with a row per source
+    Merged,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AlertStyle {
+    /// Ring the terminal bell
+    Bell,
+    /// Flash the table border until acknowledged
+    Flash,
+    /// Ring the bell and flash the border
+    Both,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedArgs {
+    pub source: Source,
+    pub label: Option<String>,
+    pub pointers: Vec<String>,
+    /// How the values at a pointer, or below it, are shown.
+    pub formats: Vec<(String, Formatter)>,
+    pub calcs: Vec<Calc>,
+    pub rules: Vec<Rule>,
+    /// Pointers whose changes run the hook.
+    pub on_change: Vec<String>,
+    pub exec_hook: Option<String>,
+    pub debounce: Duration,
+    pub polling_interval: Duration,
+    pub history: usize,
+    pub format: InputFormat,
+    pub timestamp: Option<String>,
+    pub stale_after: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ViewArgs {
+    pub name: String,
+    pub feeds: Vec<FeedArgs>,
+}
+
+/// Where on the command line an option was given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    Global,
+    View(usize),
+    Source(usize),
+}
+
+impl TJsonArgs {
+    pub fn views(&self, matches: &ArgMatches) -> Result<Vec<ViewArgs>> {
+        let mut sources: Vec<(usize, Source)> = indexed::<String>(matches, "source")
+            .into_iter()
+            .map(|(i, url)| Ok((i, Source::url(url)?)))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .chain(
+                indexed::<String>(matches, "exec")
+                    .into_iter()
+                    .map(|(i, command)| (i, Source::Exec(command))),
+            )
+            .chain(
+                indexed::<String>(matches, "sse")
+                    .into_iter()
+                    .map(|(i, url)| (i, Source::Sse(url))),
+            )
+            .chain(
+                indexed::<PathBuf>(matches, "tail")
+                    .into_iter()
+                    .map(|(i, path)| (i, Source::Tail(path))),
+            )
+            .collect();
+        sources.sort_by_key(|(i, _)| *i);
+
+        let names = indexed::<String>(matches, "view");
+        let mut markers: Vec<(usize, Scope)> = names
+            .iter()
+            .enumerate()
+            .map(|(v, (i, _))| (*i, Scope::View(v)))
+            .chain(
+                sources
+                    .iter()
+                    .enumerate()
+                    .map(|(n, (i, _))| (*i, Scope::Source(n))),
+            )
+            .collect();
+        markers.sort_by_key(|(i, _)| *i);
+
+        for (index, message) in indexed::<String>(matches, "subscribe") {
+            let websocket = match scope_at(&markers, index) {
+                Scope::Source(n) => matches!(sources[n].1, Source::WebSocket { .. }),
+                _ => false,
+            };
+            if !websocket {
+                return Err(eyre!(
+                    "--subscribe {} has to follow a ws:// or wss:// source",
+                    message
+                ));
+            }
+        }
+
+        let mut views: Vec<ViewArgs> = vec![];
+        for (n, (index, mut source)) in sources.into_iter().enumerate() {
+            let view = names.iter().rposition(|(i, _)| *i < index);
+            let scopes = [
+                Scope::Global,
+                view.map_or(Scope::Global, Scope::View),
+                Scope::Source(n),
+            ];
+
+            let interval = scoped::<u64>(matches, "polling_interval", &markers, &scopes)
+                .last()
+                .copied()
+                .unwrap_or(DEFAULT_POLLING_INTERVAL);
+
+            if let Source::WebSocket { subscribe, .. } = &mut source {
+                *subscribe =
+                    scoped::<String>(matches, "subscribe", &markers, &[Scope::Source(n)]).pop();
+            }
+
+            let (pointers, formats): (Vec<String>, Vec<_>) =
+                scoped::<String>(matches, "pointers", &markers, &scopes)
+                    .iter()
+                    .map(|pointer| Formatter::split(pointer))
+                    .map(|(pointer, formatter)| (pointer.clone(), formatter.map(|f| (pointer, f))))
+                    .unzip();
+
+            let feed = FeedArgs {
+                source,
+                label: scoped::<String>(matches, "label", &markers, &[Scope::Source(n)]).pop(),
+                pointers,
+                formats: formats.into_iter().flatten().collect(),
+                calcs: scoped::<Calc>(matches, "calc", &markers, &scopes),
+                rules: scoped::<Rule>(matches, "rules", &markers, &scopes),
+                on_change: scoped::<String>(matches, "on_change", &markers, &scopes),
+                exec_hook: scoped::<String>(matches, "exec_hook", &markers, &scopes).pop(),
+                debounce: Duration::from_secs(
+                    scoped::<u64>(matches, "debounce", &markers, &scopes)
+                        .last()
+                        .copied()
+                        .unwrap_or(DEFAULT_DEBOUNCE),
+                ),
+                polling_interval: Duration::from_secs(interval),
+                history: scoped::<usize>(matches, "history", &markers, &scopes)
+                    .last()
+                    .copied()
+                    .unwrap_or(1)
+                    .max(1),
+                format: scoped::<InputFormat>(matches, "input_format", &markers, &scopes)
+                    .last()
+                    .copied()
+                    .unwrap_or_default(),
+                timestamp: scoped::<String>(matches, "timestamp", &markers, &scopes).pop(),
+                stale_after: Duration::from_secs(
+                    scoped::<u64>(matches, "stale_after", &markers, &scopes)
+                        .last()
+                        .copied()
+                        .unwrap_or(DEFAULT_STALE_AFTER),
+                ),
+            };
+
+            let name = match view {
+                Some(v) => names[v].1.clone(),
+                None => DEFAULT_VIEW.to_string(),
+            };
+            match views.last_mut() {
+                Some(last) if last.name == name => last.feeds.push(feed),
+                _ => views.push(ViewArgs {
+                    name,
+                    feeds: vec![feed],
+                }),
+            }
+        }
+        Ok(views)
+    }
+}
+
+fn indexed<T: Clone + Send + Sync + 'static>(matches: &ArgMatches, id: &str) -> Vec<(usize, T)> {
+    match (matches.indices_of(id), matches.get_many::<T>(id)) {
+        (Some(indices), Some(values)) => indices.zip(values.cloned()).collect(),
+        _ => vec![],
+    }
+}
+
+/// Values of an argument given in any of the scopes, in command line order.
+fn scoped<T: Clone + Send + Sync + 'static>(
+    matches: &ArgMatches,
+    id: &str,
+    markers: &[(usize, Scope)],
+    scopes: &[Scope],
+) -> Vec<T> {
+    indexed::<T>(matches, id)
+        .into_iter()
+        .filter(|(index, _)| scopes.contains(&scope_at(markers, *index)))
+        .map(|(_, value)| value)
+        .collect()
+}
+
+/// The scope of the option at `index`, set by the last view or source before
+/// it.
+fn scope_at(markers: &[(usize, Scope)], index: usize) -> Scope {
+    markers
+        .iter()
+        .rev()
+        .find(|(start, _)| *start < index)
+        .map_or(Scope::Global, |(_, scope)| *scope)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use clap::{CommandFactory, FromArgMatches};
+    use eyre::Result;
+
+    use crate::{
+        args::{FeedArgs, TJsonArgs, ViewArgs},
+        format::Formatter,
+        source::Source,
+    };
+
+    fn parse(argv: &[&str]) -> Result<Vec<ViewArgs>> {
+        let matches = TJsonArgs::command().get_matches_from(argv);
+        let args = TJsonArgs::from_arg_matches(&matches).unwrap();
+        args.views(&matches)
+    }
+
+    fn views(argv: &[&str]) -> Vec<ViewArgs> {
+        parse(argv).unwrap()
+    }
+
+    fn feeds(argv: &[&str]) -> Vec<FeedArgs> {
+        views(argv).remove(0).feeds
+    }
+
+    #[test]
+    fn single_source() {
+        let f = feeds(&["tjson", "-p", "/a", "--source", "http://a", "-p", "/b"]);
+
+        assert_eq!(f.len(), 1);
+        assert_eq!(f[0].source, Source::Http("http://a".to_string()));
+        assert_eq!(f[0].pointers, vec!["/a", "/b"]);
+        assert_eq!(f[0].polling_interval, Duration::from_secs(3));
+    }
+
+    #[test]
+    fn options_belong_to_preceding_source() {
+        let f = feeds(&[
+            "tjson",
+            "-p",
+            "/shared",
+            "-i",
+            "5",
+            "--source",
+            "http://a",
+            "--label",
+            "a",
+            "-p",
+            "/a",
+            "--exec",
+            "cat b.json",
+            "-i",
+            "1",
+            "-p",
+            "/b:bytes",
+            "--assert",
+            "/b < 10",
+        ]);
+
+        assert_eq!(f.len(), 2);
+        assert_eq!(f[0].label, Some("a".to_string()));
+        assert_eq!(f[0].pointers, vec!["/shared", "/a"]);
+        assert_eq!(f[0].polling_interval, Duration::from_secs(5));
+        assert_eq!(f[1].source, Source::Exec("cat b.json".to_string()));
+        assert_eq!(f[1].label, None);
+        assert_eq!(f[1].pointers, vec!["/shared", "/b"]);
+        assert_eq!(f[1].formats, vec![("/b".to_string(), Formatter::Bytes)]);
+        assert!(f[0].rules.is_empty());
+        assert_eq!(f[1].rules[0].text, "/b < 10");
+        assert_eq!(f[1].polling_interval, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn sources_grouped_by_view() {
+        let v = views(&[
+            "tjson",
+            "-p",
+            "/shared",
+            "--view",
+            "web",
+            "-p",
+            "/web",
+            "--source",
+            "http://a",
+            "--source",
+            "http://b",
+            "-p",
+            "/b",
+            "--view",
+            "jobs",
+            "-i",
+            "10",
+            "--exec",
+            "cat jobs.json",
+        ]);
+
+        assert_eq!(v.len(), 2);
+        assert_eq!(v[0].name, "web");
+        assert_eq!(v[0].feeds.len(), 2);
+        assert_eq!(v[0].feeds[0].pointers, vec!["/shared", "/web"]);
+        assert_eq!(v[0].feeds[1].pointers, vec!["/shared", "/web", "/b"]);
+        assert_eq!(v[1].name, "jobs");
+        assert_eq!(v[1].feeds[0].pointers, vec!["/shared"]);
+        assert_eq!(v[1].feeds[0].polling_interval, Duration::from_secs(10));
+    }
+
+    #[test]
+    fn subscribe_needs_a_websocket() {
+        let f = feeds(&["tjson", "--source", "ws://a", "--subscribe", "{}"]);
+        assert!(matches!(
+            &f[0].source,
+            Source::WebSocket { subscribe: Some(s), .. } if s == "{}"
+        ));
+        assert!(parse(&["tjson", "--source", "http://a", "--subscribe", "{}"]).is_err());
+        assert!(parse(&["tjson", "--subscribe", "{}", "--source", "ws://a"]).is_err());
     }
 }
@@ -0,0 +1,178 @@
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use crate::{args::ViewArgs, expr::Outcome, feed::Feed, format::Formatter};
+
+/// How often streams are read and due feeds polled while checking.
+const TICK: Duration = Duration::from_millis(100);
+
+/// Result of a check, with the exit codes of a Nagios plugin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// Every assertion passes.
+    Ok,
+    /// Nothing failed, but not everything could be told, because a source
+    /// failed or a value is missing.
+    Warning,
+    /// An assertion fails.
+    Critical,
+}
+
+impl Status {
+    pub fn code(self) -> u8 {
+        match self {
+            Status::Ok => 0,
+            Status::Warning => 1,
+            Status::Critical => 2,
+        }
+    }
+
+    /// Feeds without assertions pass once they have a document.
+    pub fn of(feeds: &[Feed]) -> Status {
+        let mut status = Status::Ok;
+        for feed in feeds {
+            if feed.rules.is_empty() && (feed.document.is_none() || feed.error.is_some()) {
+                status = Status::Warning;
+            }
+            for outcome in feed.outcomes() {
+                match outcome {
+                    Outcome::Pass => {}
+                    Outcome::Fail => return Status::Critical,
+                    Outcome::Unknown => status = Status::Warning,
+                }
+            }
+        }
+        status
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Status::Ok => "OK",
+            Status::Warning => "WARNING",
+            Status::Critical => "CRITICAL",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Polls every source until all assertions pass or the timeout expires,
+/// prints a summary and returns the exit code.
+pub async fn run(views: Vec<ViewArgs>, timeout: Duration) -> u8 {
+    let mut feeds: Vec<Feed> = views
+        .into_iter()
+        .flat_map(|view| view.feeds)
+        .map(Feed::new)
+        .collect();
+    let deadline = Instant::now() + timeout;
+
+    let status = loop {
+        let now = Instant::now();
+        feeds
+            .iter_mut()
+            .filter(|feed| feed.is_due(now))
+            .for_each(Feed::update);
+        feeds.iter_mut().for_each(Feed::receive);
+
+        let status = Status::of(&feeds);
+        if status == Status::Ok || Instant::now() >= deadline {
+            break status;
+        }
+        tokio::time::sleep(TICK.min(deadline - Instant::now())).await;
+    };
+
+    // Polls still running at the deadline are reported as failed
+    for feed in feeds.iter_mut().filter(|feed| feed.is_fetching()) {
+        feed.error = Some(format!("No response within {}s", timeout.as_secs()));
+    }
+    report(&feeds, status);
+    status.code()
+}
+
+fn report(feeds: &[Feed], status: Status) {
+    let rules: usize = feeds.iter().map(|feed| feed.rules.len()).sum();
+    let passing: usize = feeds
+        .iter()
+        .flat_map(|feed| feed.outcomes())
+        .filter(|outcome| *outcome == Outcome::Pass)
+        .count();
+    match rules {
+        0 => println!("TJSON {}", status),
+        _ => println!(
+            "TJSON {} - {} of {} assertions pass",
+            status, passing, rules
+        ),
+    }
+
+    for feed in feeds {
+        if let Some(error) = &feed.error {
+            println!("ERROR {}: {}", feed.title(), error);
+        }
+        for (rule, outcome) in feed.rules.iter().zip(feed.outcomes()) {
+            let outcome = match outcome {
+                Outcome::Pass => "PASS",
+                Outcome::Fail => "FAIL",
+                Outcome::Unknown => "UNKNOWN",
+            };
+            println!("{} {}: {}", outcome, feed.title(), rule.text);
+        }
+        for entity in &feed.columns {
+            let value = match Formatter::find(&feed.formats, &entity.pointer) {
+                Some(formatter) => formatter.apply(&entity.value),
+                None => entity.value.to_string(),
+            };
+            println!("{} {} = {}", feed.title(), entity.pointer, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use crate::{args::FeedArgs, check::Status, decode::InputFormat, feed::Feed, source::Source};
+
+    fn feed(rules: &[&str]) -> Feed {
+        Feed::new(FeedArgs {
+            source: Source::Exec("true".to_string()),
+            label: None,
+            pointers: vec![],
+            formats: vec![],
+            calcs: vec![],
+            rules: rules.iter().map(|rule| rule.parse().unwrap()).collect(),
+            on_change: vec![],
+            exec_hook: None,
+            debounce: Duration::from_secs(5),
+            polling_interval: Duration::from_secs(1),
+            history: 1,
+            format: InputFormat::default(),
+            timestamp: None,
+            stale_after: Duration::from_secs(60),
+        })
+    }
+
+    #[test]
+    fn status() {
+        let mut healthy = feed(&["/state == \"SUCCESS\""]);
+        let mut plain = feed(&[]);
+        assert_eq!(
+            Status::of(&[feed(&["/state == \"SUCCESS\""])]),
+            Status::Warning
+        );
+        assert_eq!(Status::of(&[feed(&[])]), Status::Warning);
+
+        healthy.document = Some(json!({"state": "SUCCESS"}));
+        plain.document = Some(json!({}));
+        assert_eq!(Status::of(&[healthy]), Status::Ok);
+
+        let mut failed = feed(&["/state == \"SUCCESS\"", "/load < 0.9"]);
+        failed.document = Some(json!({"state": "FAILED"}));
+        assert_eq!(Status::of(&[failed, plain]), Status::Critical);
+        assert_eq!(Status::Critical.code(), 2);
+    }
+}
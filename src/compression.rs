@@ -0,0 +1,61 @@
+use std::{borrow::Cow, io::Read};
+
+use eyre::eyre;
+use eyre::Result;
+use flate2::read::GzDecoder;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Accept-Encoding sent with http requests. Gzip, deflate and brotli are
+/// decoded by reqwest, zstd is decoded by `decompress`.
+pub const ACCEPT_ENCODING: &str = "gzip, deflate, br, zstd";
+
+/// Decompresses gzip or zstd data, recognized by their magic numbers.
+/// Anything else is returned as is.
+pub fn decompress(bytes: &[u8]) -> Result<Cow<'_, [u8]>> {
+    if bytes.starts_with(GZIP_MAGIC) {
+        let mut out = vec![];
+        GzDecoder::new(bytes)
+            .read_to_end(&mut out)
+            .map_err(|e| eyre!("Could not decompress gzip: {}", e))?;
+        return Ok(Cow::Owned(out));
+    }
+    if bytes.starts_with(ZSTD_MAGIC) {
+        let out = zstd::decode_all(bytes).map_err(|e| eyre!("Could not decompress zstd: {}", e))?;
+        return Ok(Cow::Owned(out));
+    }
+    Ok(Cow::Borrowed(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use crate::compression::decompress;
+
+    static JSON: &[u8] = b"{\"status\": {\"done\": 1067347099}}";
+
+    #[test]
+    fn decompress_gzip() {
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(JSON).unwrap();
+        let gz = encoder.finish().unwrap();
+
+        assert_eq!(decompress(&gz).unwrap().as_ref(), JSON);
+    }
+
+    #[test]
+    fn decompress_zstd() {
+        let zst = zstd::encode_all(JSON, 3).unwrap();
+
+        assert_eq!(decompress(&zst).unwrap().as_ref(), JSON);
+    }
+
+    #[test]
+    fn plain_passthrough() {
+        assert_eq!(decompress(JSON).unwrap().as_ref(), JSON);
+    }
+}
@@ -0,0 +1,62 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use directories::ProjectDirs;
+use eyre::{eyre, Result};
+use serde::Deserialize;
+
+use crate::keymap::Action;
+
+/// Settings read from `config.toml`, for example
+///
+/// ```toml
+/// [keys]
+/// down = ["j", "down", "ctrl-n"]
+/// refresh = ["ctrl-r"]
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub keys: HashMap<Action, Vec<String>>,
+}
+
+impl Config {
+    /// Reads the given file, or `config.toml` in the user's config directory
+    /// if it exists.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match default_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Config::default()),
+            },
+        };
+        let text = fs::read_to_string(&path)
+            .map_err(|e| eyre!("Could not read {}: {}", path.display(), e))?;
+        toml::from_str(&text).map_err(|e| eyre!("Invalid config {}: {}", path.display(), e))
+    }
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "tjson").map(|dirs| dirs.config_dir().join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{config::Config, keymap::Action};
+
+    #[test]
+    fn parse_keys() {
+        let config: Config =
+            toml::from_str("[keys]\npage_down = [\"ctrl-d\", \"space\"]\n").unwrap();
+
+        assert_eq!(
+            config.keys.get(&Action::PageDown),
+            Some(&vec!["ctrl-d".to_string(), "space".to_string()])
+        );
+        assert!(toml::from_str::<Config>("[keys]\nfly = [\"x\"]\n").is_err());
+    }
+}
@@ -0,0 +1,382 @@
+use std::path::Path;
+
+use clap::ValueEnum;
+use eyre::eyre;
+use eyre::Result;
+use serde_json::{Map, Number, Value};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum InputFormat {
+    /// Guess from content type, file extension or the content itself
+    #[default]
+    Auto,
+    Json,
+    Yaml,
+    Toml,
+    Csv,
+    Msgpack,
+    Cbor,
+}
+
+impl InputFormat {
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type.split(';').next()?.trim().to_lowercase();
+        match mime.as_str() {
+            "application/json" => Some(InputFormat::Json),
+            "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml" => {
+                Some(InputFormat::Yaml)
+            }
+            "application/toml" | "text/toml" => Some(InputFormat::Toml),
+            "text/csv" => Some(InputFormat::Csv),
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+                Some(InputFormat::Msgpack)
+            }
+            "application/cbor" => Some(InputFormat::Cbor),
+            m if m.ends_with("+json") => Some(InputFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// Compressed files are named by the extension before `.gz` or `.zst`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let mut extension = path.extension()?.to_str()?.to_lowercase();
+        if matches!(extension.as_str(), "gz" | "zst" | "zstd") {
+            let stem = Path::new(path.file_stem()?);
+            extension = stem.extension()?.to_str()?.to_lowercase();
+        }
+        match extension.as_str() {
+            "json" => Some(InputFormat::Json),
+            "yaml" | "yml" => Some(InputFormat::Yaml),
+            "toml" => Some(InputFormat::Toml),
+            "csv" => Some(InputFormat::Csv),
+            "msgpack" | "mpk" => Some(InputFormat::Msgpack),
+            "cbor" => Some(InputFormat::Cbor),
+            _ => None,
+        }
+    }
+
+    /// Resolves `Auto` with the hint, if there is one.
+    pub fn or(self, hint: Option<InputFormat>) -> Self {
+        match self {
+            InputFormat::Auto => hint.unwrap_or(InputFormat::Auto),
+            format => format,
+        }
+    }
+}
+
+pub fn decode(bytes: &[u8], format: InputFormat) -> Result<Value> {
+    match format {
+        InputFormat::Json => {
+            serde_json::from_slice(bytes).map_err(|e| eyre!("Could not parse json: {}", e))
+        }
+        InputFormat::Yaml => {
+            serde_norway::from_slice(bytes).map_err(|e| eyre!("Could not parse yaml: {}", e))
+        }
+        InputFormat::Toml => {
+            let text = std::str::from_utf8(bytes)?;
+            let table: toml::Table =
+                toml::from_str(text).map_err(|e| eyre!("Could not parse toml: {}", e))?;
+            Ok(from_toml(toml::Value::Table(table)))
+        }
+        InputFormat::Csv => from_csv(bytes),
+        InputFormat::Msgpack => {
+            let value = rmpv::decode::read_value(&mut &bytes[..])
+                .map_err(|e| eyre!("Could not parse msgpack: {}", e))?;
+            from_msgpack(value, "")
+        }
+        InputFormat::Cbor => {
+            let value: ciborium::Value = ciborium::de::from_reader(bytes)
+                .map_err(|e| eyre!("Could not parse cbor: {}", e))?;
+            from_cbor(value, "")
+        }
+        InputFormat::Auto => sniff(bytes),
+    }
+}
+
+/// Json first, then toml and yaml. Yaml accepts almost any text as a plain
+/// string so it only counts when it gives a mapping or a sequence.
+fn sniff(bytes: &[u8]) -> Result<Value> {
+    let json_error = match decode(bytes, InputFormat::Json) {
+        Ok(v) => return Ok(v),
+        Err(e) => e,
+    };
+    if let Ok(v) = decode(bytes, InputFormat::Toml) {
+        return Ok(v);
+    }
+    match decode(bytes, InputFormat::Yaml) {
+        Ok(v @ (Value::Object(_) | Value::Array(_))) => Ok(v),
+        _ => Err(json_error),
+    }
+}
+
+fn from_toml(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Number::from_f64(f).map_or(Value::Null, Value::Number),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(a) => Value::Array(a.into_iter().map(from_toml).collect()),
+        toml::Value::Table(t) => {
+            Value::Object(t.into_iter().map(|(k, v)| (k, from_toml(v))).collect())
+        }
+    }
+}
+
+fn no_equivalent(kind: &str, path: &str) -> eyre::Report {
+    let path = if path.is_empty() { "/" } else { path };
+    eyre!("{} at {} has no json equivalent", kind, path)
+}
+
+fn float(f: f64, path: &str) -> Result<Value> {
+    Number::from_f64(f)
+        .map(Value::Number)
+        .ok_or_else(|| no_equivalent(&format!("Float {}", f), path))
+}
+
+/// Integer and boolean keys are turned into strings, other keys are
+/// rejected like binary data is.
+fn from_msgpack(value: rmpv::Value, path: &str) -> Result<Value> {
+    Ok(match value {
+        rmpv::Value::Nil => Value::Null,
+        rmpv::Value::Boolean(b) => Value::Bool(b),
+        rmpv::Value::Integer(i) => match (i.as_i64(), i.as_u64()) {
+            (Some(i), _) => Value::from(i),
+            (None, Some(u)) => Value::from(u),
+            (None, None) => return Err(no_equivalent("Integer", path)),
+        },
+        rmpv::Value::F32(f) => float(f as f64, path)?,
+        rmpv::Value::F64(f) => float(f, path)?,
+        rmpv::Value::String(s) => match s.into_str() {
+            Some(s) => Value::String(s),
+            None => return Err(no_equivalent("Invalid utf-8 string", path)),
+        },
+        rmpv::Value::Binary(_) => return Err(no_equivalent("Binary value", path)),
+        rmpv::Value::Ext(t, _) => {
+            return Err(no_equivalent(&format!("Extension type {}", t), path))
+        }
+        rmpv::Value::Array(a) => Value::Array(
+            a.into_iter()
+                .enumerate()
+                .map(|(i, v)| from_msgpack(v, &format!("{}/{}", path, i)))
+                .collect::<Result<_>>()?,
+        ),
+        rmpv::Value::Map(m) => {
+            let mut object = Map::new();
+            for (k, v) in m {
+                let key = match k {
+                    rmpv::Value::String(s) if s.is_str() => s.into_str().unwrap_or_default(),
+                    rmpv::Value::Integer(i) => i.to_string(),
+                    rmpv::Value::Boolean(b) => b.to_string(),
+                    _ => return Err(no_equivalent("Map key", path)),
+                };
+                let value = from_msgpack(v, &format!("{}/{}", path, key))?;
+                object.insert(key, value);
+            }
+            Value::Object(object)
+        }
+    })
+}
+
+/// Tags are dropped in favour of the tagged value.
+fn from_cbor(value: ciborium::Value, path: &str) -> Result<Value> {
+    Ok(match value {
+        ciborium::Value::Null => Value::Null,
+        ciborium::Value::Bool(b) => Value::Bool(b),
+        ciborium::Value::Integer(i) => {
+            let i = i128::from(i);
+            match (i64::try_from(i), u64::try_from(i)) {
+                (Ok(i), _) => Value::from(i),
+                (Err(_), Ok(u)) => Value::from(u),
+                _ => return Err(no_equivalent("Integer", path)),
+            }
+        }
+        ciborium::Value::Float(f) => float(f, path)?,
+        ciborium::Value::Text(s) => Value::String(s),
+        ciborium::Value::Bytes(_) => return Err(no_equivalent("Byte string", path)),
+        ciborium::Value::Tag(_, v) => from_cbor(*v, path)?,
+        ciborium::Value::Array(a) => Value::Array(
+            a.into_iter()
+                .enumerate()
+                .map(|(i, v)| from_cbor(v, &format!("{}/{}", path, i)))
+                .collect::<Result<_>>()?,
+        ),
+        ciborium::Value::Map(m) => {
+            let mut object = Map::new();
+            for (k, v) in m {
+                let key = match k {
+                    ciborium::Value::Text(s) => s,
+                    ciborium::Value::Integer(i) => i128::from(i).to_string(),
+                    ciborium::Value::Bool(b) => b.to_string(),
+                    _ => return Err(no_equivalent("Map key", path)),
+                };
+                let value = from_cbor(v, &format!("{}/{}", path, key))?;
+                object.insert(key, value);
+            }
+            Value::Object(object)
+        }
+        _ => return Err(no_equivalent("Value", path)),
+    })
+}
+
+/// Every record becomes an object keyed by the header row.
+fn from_csv(bytes: &[u8]) -> Result<Value> {
+    let mut reader = csv::Reader::from_reader(bytes);
+    let headers = reader
+        .headers()
+        .map_err(|e| eyre!("Could not parse csv: {}", e))?
+        .clone();
+
+    let mut rows = vec![];
+    for record in reader.records() {
+        let record = record.map_err(|e| eyre!("Could not parse csv: {}", e))?;
+        let row: Map<String, Value> = headers
+            .iter()
+            .zip(record.iter())
+            .map(|(key, field)| (key.to_string(), csv_field(field)))
+            .collect();
+        rows.push(Value::Object(row));
+    }
+    Ok(Value::Array(rows))
+}
+
+fn csv_field(field: &str) -> Value {
+    if field.is_empty() {
+        return Value::Null;
+    }
+    if let Ok(i) = field.parse::<i64>() {
+        return Value::from(i);
+    }
+    if let Some(n) = field.parse::<f64>().ok().and_then(Number::from_f64) {
+        return Value::Number(n);
+    }
+    match field {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => Value::String(field.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use serde_json::json;
+
+    use crate::decode::{decode, InputFormat};
+
+    #[test]
+    fn decode_yaml() {
+        let v = decode(
+            b"status:\n  state: running\n  done: 12\n",
+            InputFormat::Yaml,
+        )
+        .unwrap();
+        assert_eq!(v, json!({"status": {"state": "running", "done": 12}}));
+    }
+
+    #[test]
+    fn decode_toml() {
+        let v = decode(
+            b"[package]\nname = \"tjson\"\nreleased = 2023-11-20\n",
+            InputFormat::Toml,
+        )
+        .unwrap();
+        assert_eq!(
+            v,
+            json!({"package": {"name": "tjson", "released": "2023-11-20"}})
+        );
+    }
+
+    #[test]
+    fn decode_csv() {
+        let v = decode(b"name,load,up\nweb,0.5,true\ndb,,false\n", InputFormat::Csv).unwrap();
+        assert_eq!(
+            v,
+            json!([
+                {"name": "web", "load": 0.5, "up": true},
+                {"name": "db", "load": null, "up": false}
+            ])
+        );
+    }
+
+    #[test]
+    fn decode_msgpack() {
+        let value = rmpv::Value::Map(vec![(
+            rmpv::Value::from("status"),
+            rmpv::Value::Map(vec![
+                (rmpv::Value::from("done"), rmpv::Value::from(12)),
+                (rmpv::Value::from(7), rmpv::Value::from(0.5)),
+            ]),
+        )]);
+        let mut bytes = vec![];
+        rmpv::encode::write_value(&mut bytes, &value).unwrap();
+
+        assert_eq!(
+            decode(&bytes, InputFormat::Msgpack).unwrap(),
+            json!({"status": {"done": 12, "7": 0.5}})
+        );
+
+        let binary = rmpv::Value::Map(vec![(
+            rmpv::Value::from("blob"),
+            rmpv::Value::Binary(vec![1, 2]),
+        )]);
+        let mut bytes = vec![];
+        rmpv::encode::write_value(&mut bytes, &binary).unwrap();
+
+        assert_eq!(
+            decode(&bytes, InputFormat::Msgpack)
+                .unwrap_err()
+                .to_string(),
+            "Binary value at /blob has no json equivalent"
+        );
+    }
+
+    #[test]
+    fn decode_cbor() {
+        let value = ciborium::Value::Map(vec![(
+            ciborium::Value::Text("items".to_string()),
+            ciborium::Value::Array(vec![
+                ciborium::Value::Tag(0, Box::new(ciborium::Value::Text("2023-11-20".to_string()))),
+                ciborium::Value::Bytes(vec![0]),
+            ]),
+        )]);
+        let mut bytes = vec![];
+        ciborium::ser::into_writer(&value, &mut bytes).unwrap();
+
+        assert_eq!(
+            decode(&bytes, InputFormat::Cbor).unwrap_err().to_string(),
+            "Byte string at /items/1 has no json equivalent"
+        );
+    }
+
+    #[test]
+    fn sniff_format() {
+        assert_eq!(
+            decode(b"{\"a\": 1}", InputFormat::Auto).unwrap(),
+            json!({"a": 1})
+        );
+        assert_eq!(
+            decode(b"a = 1", InputFormat::Auto).unwrap(),
+            json!({"a": 1})
+        );
+        assert_eq!(
+            decode(b"a: [1]", InputFormat::Auto).unwrap(),
+            json!({"a": [1]})
+        );
+        assert!(decode(b"not a document", InputFormat::Auto).is_err());
+
+        assert_eq!(
+            InputFormat::from_content_type("text/yaml; charset=utf-8"),
+            Some(InputFormat::Yaml)
+        );
+        assert_eq!(
+            InputFormat::from_path(Path::new("report.CSV")),
+            Some(InputFormat::Csv)
+        );
+        assert_eq!(
+            InputFormat::from_path(Path::new("dump.yaml.zst")),
+            Some(InputFormat::Yaml)
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, List, ListItem, ListState},
+    Frame,
+};
+use serde_json::Value;
+
+use crate::json::escape_token;
+
+/// A structural change between two documents, at a json pointer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Added(String, Value),
+    Removed(String, Value),
+    Changed(String, Value, Value),
+}
+
+impl Change {
+    pub fn pointer(&self) -> &str {
+        match self {
+            Change::Added(p, _) | Change::Removed(p, _) | Change::Changed(p, _, _) => p,
+        }
+    }
+}
+
+pub fn diff(old: &Value, new: &Value) -> Vec<Change> {
+    let mut changes = vec![];
+    walk("", old, new, &mut changes);
+    changes
+}
+
+fn walk(pointer: &str, old: &Value, new: &Value, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, value) in a {
+                let p = format!("{}/{}", pointer, escape_token(key));
+                match b.get(key) {
+                    Some(other) => walk(&p, value, other, changes),
+                    None => changes.push(Change::Removed(p, value.clone())),
+                }
+            }
+            for (key, value) in b.iter().filter(|(key, _)| !a.contains_key(*key)) {
+                let p = format!("{}/{}", pointer, escape_token(key));
+                changes.push(Change::Added(p, value.clone()));
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for (i, value) in a.iter().enumerate() {
+                let p = format!("{}/{}", pointer, i);
+                match b.get(i) {
+                    Some(other) => walk(&p, value, other, changes),
+                    None => changes.push(Change::Removed(p, value.clone())),
+                }
+            }
+            for (i, value) in b.iter().enumerate().skip(a.len()) {
+                changes.push(Change::Added(format!("{}/{}", pointer, i), value.clone()));
+            }
+        }
+        (a, b) if a != b => {
+            changes.push(Change::Changed(pointer.to_string(), a.clone(), b.clone()))
+        }
+        _ => {}
+    }
+}
+
+/// List of the changes in the latest poll, a change can be picked as a
+/// column like in the explorer.
+#[derive(Debug, Default)]
+pub struct DiffView {
+    state: ListState,
+}
+
+impl DiffView {
+    pub fn new() -> Self {
+        Self {
+            state: ListState::default().with_selected(Some(0)),
+        }
+    }
+
+    pub fn selected<'a>(&self, changes: &'a [Change]) -> Option<&'a Change> {
+        changes.get(self.state.selected().unwrap_or(0))
+    }
+
+    pub fn up(&mut self) {
+        let selected = self.state.selected().unwrap_or(0);
+        self.state.select(Some(selected.saturating_sub(1)));
+    }
+
+    pub fn down(&mut self, len: usize) {
+        let selected = self.state.selected().unwrap_or(0);
+        self.state
+            .select(Some((selected + 1).min(len.saturating_sub(1))));
+    }
+
+    pub fn render(
+        &mut self,
+        f: &mut Frame,
+        area: Rect,
+        title: String,
+        changes: &[Change],
+        pointers: &[String],
+    ) {
+        if self.state.selected().unwrap_or(0) >= changes.len() {
+            self.state.select(Some(changes.len().saturating_sub(1)));
+        }
+
+        let items: Vec<ListItem> = match changes.is_empty() {
+            true => vec![ListItem::new("No changes since the last poll")],
+            false => changes
+                .iter()
+                .map(|change| {
+                    let (line, color) = match change {
+                        Change::Added(p, v) => (format!("+ {}: {}", p, v), Color::Green),
+                        Change::Removed(p, v) => (format!("- {}: {}", p, v), Color::Red),
+                        Change::Changed(p, old, new) => {
+                            (format!("~ {}: {} → {}", p, old, new), Color::Yellow)
+                        }
+                    };
+                    let mut style = Style::default().fg(color);
+                    if pointers.iter().any(|p| p == change.pointer()) {
+                        style = style.add_modifier(Modifier::BOLD);
+                    }
+                    ListItem::new(line).style(style)
+                })
+                .collect(),
+        };
+
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        f.render_stateful_widget(list, area, &mut self.state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::diff::{diff, Change};
+
+    #[test]
+    fn structural_changes() {
+        let old = json!({"status": {"state": "running", "done": 1, "gone": true}, "load": [1, 2]});
+        let new =
+            json!({"status": {"state": "FAILED", "done": 1, "new/key": null}, "load": [1, 3, 4]});
+
+        assert_eq!(
+            diff(&old, &new),
+            vec![
+                Change::Changed("/load/1".to_string(), json!(2), json!(3)),
+                Change::Added("/load/2".to_string(), json!(4)),
+                Change::Removed("/status/gone".to_string(), json!(true)),
+                Change::Changed(
+                    "/status/state".to_string(),
+                    json!("running"),
+                    json!("FAILED")
+                ),
+                Change::Added("/status/new~1key".to_string(), json!(null)),
+            ]
+        );
+    }
+
+    #[test]
+    fn type_change() {
+        assert_eq!(
+            diff(&json!({"a": {"b": 1}}), &json!({"a": [1]})),
+            vec![Change::Changed(
+                "/a".to_string(),
+                json!({"b": 1}),
+                json!([1])
+            )]
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use eyre::eyre;
+use eyre::Result;
+use tokio::process::Command;
+
+use crate::{
+    decode::{decode, InputFormat},
+    source::Fetched,
+};
+
+pub async fn run(command: &str, format: InputFormat) -> Result<Fetched> {
+    let output = shell(command).output().await?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(eyre!(
+            "Command failed ({}) {}",
+            output.status,
+            stderr.trim()
+        ));
+    }
+
+    match decode(&output.stdout, format) {
+        Ok(value) => Ok(Fetched {
+            value,
+            size: output.stdout.len(),
+            status: None,
+        }),
+        // The decode error already says what could not be parsed
+        Err(e) => match String::from_utf8_lossy(&output.stderr).trim() {
+            "" => Err(e),
+            stderr => Err(eyre!("{} {}", e, stderr)),
+        },
+    }
+}
+
+#[cfg(not(windows))]
+pub fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command).kill_on_drop(true);
+    cmd
+}
+
+#[cfg(windows)]
+pub fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command).kill_on_drop(true);
+    cmd
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use serde_json::json;
+
+    use crate::{decode::InputFormat, exec::run};
+
+    #[tokio::test]
+    async fn parse_stdout() {
+        let fetched = run("echo '{\"status\": {\"done\": 12}}'", InputFormat::Json)
+            .await
+            .unwrap();
+        assert_eq!(fetched.value, json!({"status": {"done": 12}}));
+        assert_eq!(fetched.size, 25);
+    }
+
+    #[tokio::test]
+    async fn failing_command() {
+        let e = run("echo broken >&2; exit 3", InputFormat::Auto)
+            .await
+            .unwrap_err();
+        assert!(e.to_string().contains("broken"));
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::HashSet;
+
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, List, ListItem, ListState},
+    Frame,
+};
+use serde_json::Value;
+
+use crate::json::escape_token;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeKind {
+    Object(usize),
+    Array(usize),
+    Scalar(String),
+}
+
+/// A visible line in the tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    pub pointer: String,
+    pub key: String,
+    pub depth: usize,
+    pub kind: NodeKind,
+}
+
+/// Collapsible tree of a json document, used to pick the pointers shown in
+/// the table.
+#[derive(Debug, Default)]
+pub struct Explorer {
+    expanded: HashSet<String>,
+    state: ListState,
+}
+
+impl Explorer {
+    pub fn new() -> Self {
+        Self {
+            expanded: HashSet::new(),
+            state: ListState::default().with_selected(Some(0)),
+        }
+    }
+
+    pub fn nodes(&self, document: &Value) -> Vec<Node> {
+        let mut nodes = vec![];
+        match document {
+            Value::Object(_) | Value::Array(_) => self.children(document, "", 0, &mut nodes),
+            scalar => nodes.push(Node {
+                pointer: String::new(),
+                key: String::new(),
+                depth: 0,
+                kind: NodeKind::Scalar(scalar.to_string()),
+            }),
+        }
+        nodes
+    }
+
+    fn children(&self, value: &Value, pointer: &str, depth: usize, nodes: &mut Vec<Node>) {
+        let entries: Vec<(String, &Value)> = match value {
+            Value::Object(map) => map.iter().map(|(k, v)| (k.clone(), v)).collect(),
+            Value::Array(array) => array
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (i.to_string(), v))
+                .collect(),
+            _ => return,
+        };
+
+        for (key, child) in entries {
+            let child_pointer = format!("{}/{}", pointer, escape_token(&key));
+            let kind = match child {
+                Value::Object(map) => NodeKind::Object(map.len()),
+                Value::Array(array) => NodeKind::Array(array.len()),
+                scalar => NodeKind::Scalar(scalar.to_string()),
+            };
+            let expanded = self.expanded.contains(&child_pointer);
+            nodes.push(Node {
+                pointer: child_pointer.clone(),
+                key,
+                depth,
+                kind,
+            });
+            if expanded {
+                self.children(child, &child_pointer, depth + 1, nodes);
+            }
+        }
+    }
+
+    pub fn selected<'a>(&self, nodes: &'a [Node]) -> Option<&'a Node> {
+        nodes.get(self.state.selected().unwrap_or(0))
+    }
+
+    pub fn up(&mut self) {
+        let selected = self.state.selected().unwrap_or(0);
+        self.state.select(Some(selected.saturating_sub(1)));
+    }
+
+    pub fn down(&mut self, len: usize) {
+        let selected = self.state.selected().unwrap_or(0);
+        self.state
+            .select(Some((selected + 1).min(len.saturating_sub(1))));
+    }
+
+    pub fn expand(&mut self, node: &Node) {
+        if !matches!(node.kind, NodeKind::Scalar(_)) {
+            self.expanded.insert(node.pointer.clone());
+        }
+    }
+
+    /// Collapses the node, or moves to its parent when already collapsed.
+    pub fn collapse(&mut self, nodes: &[Node]) {
+        let Some(node) = self.selected(nodes) else {
+            return;
+        };
+        if self.expanded.remove(&node.pointer) {
+            return;
+        }
+        let index = self.state.selected().unwrap_or(0);
+        if let Some(parent) = nodes[..index].iter().rposition(|n| n.depth < node.depth) {
+            self.state.select(Some(parent));
+        }
+    }
+
+    pub fn toggle(&mut self, node: &Node) {
+        if !self.expanded.remove(&node.pointer) {
+            self.expand(node);
+        }
+    }
+
+    pub fn render(
+        &mut self,
+        f: &mut Frame,
+        area: Rect,
+        title: String,
+        document: &Value,
+        pointers: &[String],
+    ) {
+        let nodes = self.nodes(document);
+        if self.state.selected().unwrap_or(0) >= nodes.len() {
+            self.state.select(Some(nodes.len().saturating_sub(1)));
+        }
+
+        let items: Vec<ListItem> = nodes
+            .iter()
+            .map(|node| {
+                let picked = pointers.contains(&node.pointer);
+                let marker = match (&node.kind, self.expanded.contains(&node.pointer)) {
+                    (NodeKind::Scalar(_), _) => " ",
+                    (_, true) => "▾",
+                    (_, false) => "▸",
+                };
+                let summary = match &node.kind {
+                    NodeKind::Object(n) => format!(" {{{}}}", n),
+                    NodeKind::Array(n) => format!(" [{}]", n),
+                    NodeKind::Scalar(v) => format!(": {}", v),
+                };
+                let line = format!(
+                    "{}{} {} {}{}",
+                    "  ".repeat(node.depth),
+                    if picked { "[x]" } else { "[ ]" },
+                    marker,
+                    node.key,
+                    summary
+                );
+                let style = match picked {
+                    true => Style::default().fg(Color::Green),
+                    false => Style::default(),
+                };
+                ListItem::new(line).style(style)
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        f.render_stateful_widget(list, area, &mut self.state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::explorer::{Explorer, NodeKind};
+
+    #[test]
+    fn expand_and_collapse() {
+        let doc = json!({"status": {"state": "running", "a/b": 1}, "load": [1, 3]});
+        let mut explorer = Explorer::new();
+
+        let nodes = explorer.nodes(&doc);
+        assert_eq!(
+            nodes.iter().map(|n| n.pointer.as_str()).collect::<Vec<_>>(),
+            vec!["/load", "/status"]
+        );
+        assert_eq!(nodes[0].kind, NodeKind::Array(2));
+
+        explorer.expand(&nodes[1]);
+        let nodes = explorer.nodes(&doc);
+        assert_eq!(
+            nodes.iter().map(|n| n.pointer.as_str()).collect::<Vec<_>>(),
+            vec!["/load", "/status", "/status/a~1b", "/status/state"]
+        );
+        assert_eq!(nodes[2].depth, 1);
+
+        explorer.down(nodes.len());
+        explorer.down(nodes.len());
+        explorer.collapse(&nodes);
+        assert_eq!(explorer.selected(&nodes).unwrap().pointer, "/status");
+        explorer.collapse(&nodes);
+        assert_eq!(explorer.nodes(&doc).len(), 2);
+    }
+}
@@ -0,0 +1,541 @@
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use eyre::{eyre, Result};
+use serde_json::Value;
+
+use crate::{format::Formatter, json::JsonValue};
+
+/// An expression over the values in a document, like
+/// `/status/done / (/status/done + /status/left) * 100` or
+/// `/status/state == "SUCCESS" && /status/load < 0.9`.
+///
+/// A `/` where a value is expected starts a pointer that runs until the
+/// next space or parenthesis, anywhere else it divides.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+    Null,
+    Pointer(String),
+    Negate(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(Box<Expr>, Op, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    And,
+    Or,
+}
+
+/// What an expression evaluates to. Anything that can't be computed, like
+/// a missing or non-numeric value or a division by zero, is null.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Val {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+    Null,
+}
+
+impl Val {
+    fn from_json(value: Option<&Value>) -> Val {
+        match value {
+            Some(Value::Number(n)) => n.as_f64().map_or(Val::Null, Val::Number),
+            Some(Value::String(s)) => Val::Text(s.clone()),
+            Some(Value::Bool(b)) => Val::Bool(*b),
+            _ => Val::Null,
+        }
+    }
+
+    /// Numbers in strings are used as numbers.
+    fn number(&self) -> Option<f64> {
+        match self {
+            Val::Number(n) => Some(*n),
+            Val::Text(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn bool(&self) -> Option<bool> {
+        match self {
+            Val::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Numbers are compared by value, text by text and booleans with each
+    /// other. Anything else can't be ordered.
+    fn compare(&self, other: &Val) -> Option<Ordering> {
+        match (self, other) {
+            (Val::Text(a), Val::Text(b)) if self.number().is_none() || other.number().is_none() => {
+                Some(a.cmp(b))
+            }
+            (Val::Bool(a), Val::Bool(b)) => Some(a.cmp(b)),
+            _ => self.number()?.partial_cmp(&other.number()?),
+        }
+    }
+
+    /// Whole numbers are shown as integers.
+    pub fn to_json_value(&self) -> JsonValue {
+        match self {
+            Val::Number(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => {
+                JsonValue::PosInt(*n as i64)
+            }
+            Val::Number(n) => JsonValue::Float(*n),
+            Val::Text(s) => JsonValue::Text(s.clone()),
+            Val::Bool(b) => JsonValue::Boolean(*b),
+            Val::Null => JsonValue::Null,
+        }
+    }
+}
+
+impl fmt::Display for Val {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_json_value())
+    }
+}
+
+impl Expr {
+    pub fn eval(&self, document: &Value) -> Val {
+        match self {
+            Expr::Number(n) => Val::Number(*n),
+            Expr::Text(s) => Val::Text(s.clone()),
+            Expr::Bool(b) => Val::Bool(*b),
+            Expr::Null => Val::Null,
+            Expr::Pointer(pointer) => Val::from_json(document.pointer(pointer)),
+            Expr::Negate(expr) => match expr.eval(document).number() {
+                Some(n) => Val::Number(-n),
+                None => Val::Null,
+            },
+            Expr::Not(expr) => match expr.eval(document).bool() {
+                Some(b) => Val::Bool(!b),
+                None => Val::Null,
+            },
+            Expr::Binary(left, op, right) => {
+                let (a, b) = (left.eval(document), right.eval(document));
+                match op {
+                    Op::And => match (a.bool(), b.bool()) {
+                        (Some(false), _) | (_, Some(false)) => Val::Bool(false),
+                        (Some(true), Some(true)) => Val::Bool(true),
+                        _ => Val::Null,
+                    },
+                    Op::Or => match (a.bool(), b.bool()) {
+                        (Some(true), _) | (_, Some(true)) => Val::Bool(true),
+                        (Some(false), Some(false)) => Val::Bool(false),
+                        _ => Val::Null,
+                    },
+                    Op::Equal | Op::NotEqual => {
+                        // A missing value is only known to differ from an
+                        // explicit null
+                        let explicit =
+                            matches!(**left, Expr::Null) || matches!(**right, Expr::Null);
+                        let equal = match (&a, &b) {
+                            (Val::Null, Val::Null) => true,
+                            (Val::Null, _) | (_, Val::Null) if !explicit => return Val::Null,
+                            _ => a.compare(&b) == Some(Ordering::Equal),
+                        };
+                        Val::Bool(equal == (*op == Op::Equal))
+                    }
+                    Op::Less | Op::LessOrEqual | Op::Greater | Op::GreaterOrEqual => {
+                        match a.compare(&b) {
+                            Some(ordering) => Val::Bool(match op {
+                                Op::Less => ordering.is_lt(),
+                                Op::LessOrEqual => ordering.is_le(),
+                                Op::Greater => ordering.is_gt(),
+                                _ => ordering.is_ge(),
+                            }),
+                            None => Val::Null,
+                        }
+                    }
+                    _ => arithmetic(*op, &a, &b),
+                }
+            }
+        }
+    }
+}
+
+fn arithmetic(op: Op, a: &Val, b: &Val) -> Val {
+    let (Some(a), Some(b)) = (a.number(), b.number()) else {
+        return Val::Null;
+    };
+    let n = match op {
+        Op::Add => a + b,
+        Op::Subtract => a - b,
+        Op::Multiply => a * b,
+        Op::Divide => a / b,
+        _ => a % b,
+    };
+    match n.is_finite() {
+        true => Val::Number(n),
+        false => Val::Null,
+    }
+}
+
+impl FromStr for Expr {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let tokens = tokenize(s)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let expr = parser.expr()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(token) => Err(eyre!("Unexpected {} in {}", token, s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Text(String),
+    Word(String),
+    Pointer(String),
+    Op(&'static str),
+    Open,
+    Close,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Text(s) => write!(f, "\"{}\"", s),
+            Token::Word(w) => write!(f, "{}", w),
+            Token::Pointer(p) => write!(f, "{}", p),
+            Token::Op(op) => write!(f, "{}", op),
+            Token::Open => write!(f, "("),
+            Token::Close => write!(f, ")"),
+        }
+    }
+}
+
+/// Longer operators first so `<=` isn't read as `<`.
+const OPS: [&str; 15] = [
+    "==", "!=", "<=", ">=", "&&", "||", "<", ">", "!", "+", "-", "*", "/", "%", "=",
+];
+
+fn tokenize(s: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut rest = s;
+    while let Some(c) = rest.chars().next() {
+        // A value is expected at the start, after an operator and after `(`
+        let operand = matches!(tokens.last(), None | Some(Token::Op(_)) | Some(Token::Open));
+        let (token, len) = match c {
+            c if c.is_whitespace() => {
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+            '(' => (Token::Open, 1),
+            ')' => (Token::Close, 1),
+            // `/` and `-` are common in keys, so only these end a pointer
+            '/' if operand => {
+                let len = rest
+                    .find(|c: char| c.is_whitespace() || "()+*%<>=!&|".contains(c))
+                    .unwrap_or(rest.len());
+                (Token::Pointer(rest[..len].to_string()), len)
+            }
+            '"' | '\'' => {
+                let mut text = String::new();
+                let mut chars = rest.char_indices().skip(1);
+                let end = loop {
+                    match chars.next() {
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, escaped)) => text.push(escaped),
+                            None => return Err(eyre!("Unterminated string in {}", s)),
+                        },
+                        Some((i, q)) if q == c => break i,
+                        Some((_, other)) => text.push(other),
+                        None => return Err(eyre!("Unterminated string in {}", s)),
+                    }
+                };
+                (Token::Text(text), end + 1)
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let len = rest
+                    .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E'))
+                    .unwrap_or(rest.len());
+                let n = rest[..len]
+                    .parse()
+                    .map_err(|_| eyre!("Invalid number {} in {}", &rest[..len], s))?;
+                (Token::Number(n), len)
+            }
+            c if c.is_alphabetic() => {
+                let len = rest
+                    .find(|c: char| !c.is_alphanumeric())
+                    .unwrap_or(rest.len());
+                (Token::Word(rest[..len].to_string()), len)
+            }
+            _ => match OPS.iter().find(|op| rest.starts_with(*op)) {
+                Some(&"=") => return Err(eyre!("Use == to compare in {}", s)),
+                Some(op) => (Token::Op(op), op.len()),
+                None => return Err(eyre!("Unexpected {} in {}", c, s)),
+            },
+        };
+        tokens.push(token);
+        rest = &rest[len..];
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    /// Operators of one precedence level, applied left to right.
+    fn binary(
+        &mut self,
+        ops: &[(&str, Op)],
+        operand: fn(&mut Parser) -> Result<Expr>,
+    ) -> Result<Expr> {
+        let mut expr = operand(self)?;
+        while let Some(Token::Op(token)) = self.peek() {
+            let Some((_, op)) = ops.iter().find(|(o, _)| o == token) else {
+                break;
+            };
+            let op = *op;
+            self.next();
+            expr = Expr::Binary(Box::new(expr), op, Box::new(operand(self)?));
+        }
+        Ok(expr)
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        self.binary(&[("||", Op::Or)], Parser::and)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        self.binary(&[("&&", Op::And)], Parser::comparison)
+    }
+
+    fn comparison(&mut self) -> Result<Expr> {
+        self.binary(
+            &[
+                ("==", Op::Equal),
+                ("!=", Op::NotEqual),
+                ("<", Op::Less),
+                ("<=", Op::LessOrEqual),
+                (">", Op::Greater),
+                (">=", Op::GreaterOrEqual),
+            ],
+            Parser::sum,
+        )
+    }
+
+    fn sum(&mut self) -> Result<Expr> {
+        self.binary(&[("+", Op::Add), ("-", Op::Subtract)], Parser::term)
+    }
+
+    fn term(&mut self) -> Result<Expr> {
+        self.binary(
+            &[("*", Op::Multiply), ("/", Op::Divide), ("%", Op::Remainder)],
+            Parser::unary,
+        )
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::Op("-")) => Ok(Expr::Negate(Box::new(self.unary()?))),
+            Some(Token::Op("!")) => Ok(Expr::Not(Box::new(self.unary()?))),
+            Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::Text(s)) => Ok(Expr::Text(s)),
+            Some(Token::Pointer(p)) => Ok(Expr::Pointer(p)),
+            Some(Token::Word(word)) => match word.as_str() {
+                "true" => Ok(Expr::Bool(true)),
+                "false" => Ok(Expr::Bool(false)),
+                "null" => Ok(Expr::Null),
+                _ => Err(eyre!("Unknown word {}, strings need quotes", word)),
+            },
+            Some(Token::Open) => {
+                let expr = self.expr()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err(eyre!("Missing )")),
+                }
+            }
+            Some(token) => Err(eyre!("Unexpected {}", token)),
+            None => Err(eyre!("Unexpected end of expression")),
+        }
+    }
+}
+
+/// A computed column, `--calc 'name=expression'`. The name may be followed
+/// by a formatter like `pct:fixed2=...`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calc {
+    pub name: String,
+    pub expr: Expr,
+    pub formatter: Option<Formatter>,
+}
+
+impl Calc {
+    /// Computed columns are told apart from document values by their pointer.
+    pub fn pointer(&self) -> String {
+        format!("calc:{}", self.name)
+    }
+}
+
+impl FromStr for Calc {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, expr) = s
+            .split_once('=')
+            .ok_or_else(|| eyre!("Expected name=expression, got {}", s))?;
+        let (name, formatter) = Formatter::split(name.trim());
+        Ok(Calc {
+            name,
+            expr: expr.parse()?,
+            formatter,
+        })
+    }
+}
+
+/// An assertion about a document, `--assert '/status/load < 0.9'`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub text: String,
+    pub expr: Expr,
+}
+
+/// Whether a rule holds for a document. A rule that evaluates to anything
+/// but a boolean, for example because a value is missing, is unknown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Pass,
+    Fail,
+    Unknown,
+}
+
+impl Rule {
+    pub fn check(&self, document: &Value) -> Outcome {
+        match self.expr.eval(document) {
+            Val::Bool(true) => Outcome::Pass,
+            Val::Bool(false) => Outcome::Fail,
+            _ => Outcome::Unknown,
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(Rule {
+            text: s.trim().to_string(),
+            expr: s.parse()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::expr::{Calc, Expr, Outcome, Rule, Val};
+
+    fn eval(expr: &str) -> Val {
+        let document =
+            json!({"status": {"done": 3, "left": 1, "name": "x", "text": "2", "none": null}});
+        expr.parse::<Expr>().unwrap().eval(&document)
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(
+            eval("/status/done / (/status/done + /status/left) * 100"),
+            Val::Number(75.0)
+        );
+        assert_eq!(eval("1 + 2 * 3 - -1"), Val::Number(8.0));
+        assert_eq!(eval("(1 + 2) * 3 % 4"), Val::Number(1.0));
+        assert_eq!(eval("/status/text * 2"), Val::Number(4.0));
+        assert_eq!(eval("/status/done*100"), Val::Number(300.0));
+        assert_eq!(eval("/status/done+/status/left"), Val::Number(4.0));
+        assert_eq!(eval("/status/done%2"), Val::Number(1.0));
+    }
+
+    #[test]
+    fn undefined_values_are_null() {
+        assert_eq!(eval("/status/missing + 1"), Val::Null);
+        assert_eq!(eval("/status/none + 1"), Val::Null);
+        assert_eq!(eval("/status/name + 1"), Val::Null);
+        assert_eq!(eval("/status/done / 0"), Val::Null);
+    }
+
+    #[test]
+    fn comparisons() {
+        assert_eq!(eval("/status/name == \"x\""), Val::Bool(true));
+        assert_eq!(eval("/status/name != 'x'"), Val::Bool(false));
+        assert_eq!(eval("/status/text == 2"), Val::Bool(true));
+        assert_eq!(
+            eval("/status/done >= 3 && /status/left < 0.9"),
+            Val::Bool(false)
+        );
+        assert_eq!(
+            eval("/status/done > 10 || !(/status/left > 1)"),
+            Val::Bool(true)
+        );
+        assert_eq!(eval("/status/left<0.9||/status/done>2"), Val::Bool(true));
+        assert_eq!(eval("/status/none == null"), Val::Bool(true));
+        assert_eq!(eval("/status/done != null"), Val::Bool(true));
+        assert_eq!(eval("/status/missing == 1"), Val::Null);
+        assert_eq!(eval("/status/missing < 1 || true"), Val::Bool(true));
+    }
+
+    #[test]
+    fn rules() {
+        let document = json!({"status": {"state": "FAILED"}});
+        let check = |rule: &str| rule.parse::<Rule>().unwrap().check(&document);
+
+        assert_eq!(check("/status/state == \"FAILED\""), Outcome::Pass);
+        assert_eq!(check("/status/state == \"SUCCESS\""), Outcome::Fail);
+        assert_eq!(check("/status/load < 0.9"), Outcome::Unknown);
+        assert_eq!(check("/status/state"), Outcome::Unknown);
+        assert_eq!(check("/status/state==\"FAILED\""), Outcome::Pass);
+        assert_eq!(check("/status/state!='FAILED'"), Outcome::Fail);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!("1 +".parse::<Expr>().is_err());
+        assert!("(1 + 2".parse::<Expr>().is_err());
+        assert!("1 2".parse::<Expr>().is_err());
+        assert!("1 & 2".parse::<Expr>().is_err());
+        assert!("/a = 1".parse::<Expr>().is_err());
+        assert!("/a == FAILED".parse::<Expr>().is_err());
+        assert!("/a == \"open".parse::<Expr>().is_err());
+        assert!("pct".parse::<Calc>().is_err());
+
+        let calc: Calc = "pct:fixed2 = /a / /b".parse().unwrap();
+        assert_eq!(calc.name, "pct");
+        assert!(calc.formatter.is_some());
+    }
+}
@@ -0,0 +1,392 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local, Utc};
+use eyre::Result;
+use serde_json::Value;
+use tokio::sync::{
+    mpsc::Receiver,
+    oneshot::{self, error::TryRecvError},
+};
+
+use crate::{
+    args::FeedArgs,
+    decode::InputFormat,
+    diff::{diff, Change},
+    expr::{Calc, Outcome, Rule},
+    format::Formatter,
+    hook::Hook,
+    json::{get_cell, EntityResult, JsonEntity},
+    source::{Fetched, ResponseError, Source},
+    table::Cursor,
+    timestamp,
+};
+
+/// A source together with the pointers picked from it and the latest result.
+pub struct Feed {
+    pub source: Source,
+    pub label: Option<String>,
+    pub pointers: Vec<String>,
+    pub formats: Vec<(String, Formatter)>,
+    /// Columns computed from the document.
+    pub calcs: Vec<Calc>,
+    /// Assertions the document should pass.
+    pub rules: Vec<Rule>,
+    pub polling_interval: Duration,
+    pub format: InputFormat,
+    /// When a rule last started failing, until it is acknowledged.
+    pub alert: Option<Instant>,
+    /// Command run when watched values change or rules start failing.
+    pub hook: Option<Hook>,
+    /// Pointer to the time the document was produced.
+    pub timestamp: Option<String>,
+    pub stale_after: Duration,
+    pub columns: Vec<JsonEntity>,
+    /// The latest document fetched from the source.
+    pub document: Option<Value>,
+    /// What changed in the document with the latest result.
+    pub changes: Vec<Change>,
+    /// Results before the latest one, newest first.
+    pub history: VecDeque<Vec<JsonEntity>>,
+    history_len: usize,
+    pub error: Option<String>,
+    /// When a result was last applied.
+    pub last_success: Option<DateTime<Local>>,
+    /// How long the latest poll took.
+    pub latency: Option<Duration>,
+    /// Status code of the latest http response.
+    pub status: Option<u16>,
+    /// Size in bytes of the latest polled payload.
+    pub size: Option<usize>,
+    pub cursor: Cursor,
+    pub update_source_c: usize,
+    /// When the value at each column pointer last changed.
+    changed: HashMap<String, Instant>,
+    last_updated: Option<Instant>,
+    stream: Option<Receiver<Result<Value>>>,
+    /// Poll running in the background and how long it took.
+    fetching: Option<oneshot::Receiver<(Result<Fetched>, Duration)>>,
+}
+
+impl Feed {
+    pub fn new(args: FeedArgs) -> Self {
+        Self {
+            source: args.source,
+            label: args.label,
+            pointers: args.pointers,
+            formats: args
+                .formats
+                .into_iter()
+                .chain(
+                    args.calcs
+                        .iter()
+                        .filter_map(|calc| Some((calc.pointer(), calc.formatter?))),
+                )
+                .collect(),
+            calcs: args.calcs,
+            rules: args.rules,
+            alert: None,
+            hook: args
+                .exec_hook
+                .map(|command| Hook::new(command, args.on_change, args.debounce)),
+            polling_interval: args.polling_interval,
+            format: args.format,
+            timestamp: args.timestamp,
+            stale_after: args.stale_after,
+            columns: vec![],
+            document: None,
+            changes: vec![],
+            history: VecDeque::new(),
+            history_len: args.history,
+            error: None,
+            last_success: None,
+            latency: None,
+            status: None,
+            size: None,
+            cursor: Cursor::default(),
+            update_source_c: 0,
+            changed: HashMap::new(),
+            last_updated: None,
+            stream: None,
+            fetching: None,
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match &self.label {
+            Some(label) => label.clone(),
+            None => self.source.to_string(),
+        }
+    }
+
+    /// Streaming feeds are only due until they are subscribed to and polled
+    /// feeds are not due while a poll is still running.
+    pub fn is_due(&self, now: Instant) -> bool {
+        if self.source.is_stream() {
+            return self.stream.is_none();
+        }
+        if self.is_fetching() {
+            return false;
+        }
+        match self.last_updated {
+            Some(last) => now - last >= self.polling_interval,
+            None => true,
+        }
+    }
+
+    /// Subscribes to a stream or starts a poll in the background, results
+    /// are applied by `receive` so a source that doesn't answer holds up
+    /// nothing else.
+    pub fn update(&mut self) {
+        self.last_updated = Some(Instant::now());
+        match self.source.is_stream() {
+            true => self.stream = self.source.subscribe(),
+            false => {
+                let (sender, receiver) = oneshot::channel();
+                let source = self.source.clone();
+                let format = self.format;
+                tokio::spawn(async move {
+                    let started = Instant::now();
+                    let result = source.fetch(format).await;
+                    let _ = sender.send((result, started.elapsed()));
+                });
+                self.fetching = Some(receiver);
+            }
+        }
+    }
+
+    pub fn is_fetching(&self) -> bool {
+        self.fetching.is_some()
+    }
+
+    fn fetched(&mut self, result: Result<Fetched>, latency: Duration) {
+        self.latency = Some(latency);
+        (self.status, self.size) = match &result {
+            Ok(fetched) => (fetched.status, Some(fetched.size)),
+            Err(e) => match e.downcast_ref::<ResponseError>() {
+                Some(response) => (Some(response.status), Some(response.size)),
+                None => (None, None),
+            },
+        };
+        self.apply(result.map(|fetched| fetched.value));
+    }
+
+    /// Time left until the feed is polled again, streaming feeds are not
+    /// polled.
+    pub fn next_poll(&self, now: Instant) -> Option<Duration> {
+        if self.source.is_stream() {
+            return None;
+        }
+        match self.last_updated {
+            Some(last) => Some(self.polling_interval.saturating_sub(now - last)),
+            None => Some(Duration::ZERO),
+        }
+    }
+
+    /// Time since the document's own timestamp, if it has one.
+    pub fn age(&self) -> Option<Duration> {
+        let pointer = self.timestamp.as_ref()?;
+        let value = self.document.as_ref()?.pointer(pointer)?;
+        let produced = timestamp::parse(value)?;
+        Some((Utc::now() - produced).to_std().unwrap_or(Duration::ZERO))
+    }
+
+    /// Whether the document's timestamp is older than `stale_after`.
+    pub fn is_stale(&self) -> bool {
+        self.age().is_some_and(|age| age > self.stale_after)
+    }
+
+    /// How every rule fares against the latest document. Nothing can be told
+    /// while there is no document or the latest poll failed.
+    pub fn outcomes(&self) -> Vec<Outcome> {
+        match self.error {
+            Some(_) => vec![Outcome::Unknown; self.rules.len()],
+            None => self.checked(),
+        }
+    }
+
+    /// How every rule fares against the last document fetched, also while
+    /// later polls fail.
+    fn checked(&self) -> Vec<Outcome> {
+        self.rules
+            .iter()
+            .map(|rule| match &self.document {
+                Some(document) => rule.check(document),
+                None => Outcome::Unknown,
+            })
+            .collect()
+    }
+
+    /// Number of rules the latest document fails.
+    pub fn failing(&self) -> usize {
+        self.outcomes()
+            .into_iter()
+            .filter(|outcome| *outcome == Outcome::Fail)
+            .count()
+    }
+
+    pub fn acknowledge(&mut self) {
+        self.alert = None;
+    }
+
+    /// Makes a polled feed due right away.
+    pub fn expire(&mut self) {
+        self.last_updated = None;
+    }
+
+    /// Applies a finished poll and the documents a streaming source has
+    /// pushed since last call.
+    pub fn receive(&mut self) {
+        if let Some(mut fetching) = self.fetching.take() {
+            match fetching.try_recv() {
+                Ok((result, latency)) => self.fetched(result, latency),
+                Err(TryRecvError::Empty) => self.fetching = Some(fetching),
+                Err(TryRecvError::Closed) => {}
+            }
+        }
+        let mut received = vec![];
+        if let Some(stream) = &mut self.stream {
+            while let Ok(result) = stream.try_recv() {
+                received.push(result);
+            }
+        }
+        for result in received {
+            self.apply(result);
+        }
+        let title = self.title();
+        if let Some(hook) = &mut self.hook {
+            for event in hook.due(Instant::now()) {
+                hook.run(&title, &event);
+            }
+        }
+    }
+
+    pub fn last_changed(&self, pointer: &str) -> Option<Instant> {
+        self.changed.get(pointer).copied()
+    }
+
+    /// Adds the pointer as a column, or removes it if it is already shown.
+    pub fn toggle_pointer(&mut self, pointer: &str) {
+        match self.pointers.iter().position(|p| p == pointer) {
+            Some(i) => {
+                self.pointers.remove(i);
+            }
+            None => self.pointers.push(pointer.to_string()),
+        }
+        if let Some(json) = &self.document {
+            self.columns = self.extract(json);
+        }
+    }
+
+    fn extract(&self, json: &Value) -> Vec<JsonEntity> {
+        self.pointers
+            .iter()
+            .filter_map(|pointer| get_cell(json, pointer))
+            .flat_map(|v| -> Vec<JsonEntity> {
+                match v {
+                    EntityResult::Entities(cs) => cs,
+                    EntityResult::Entity(c) => vec![c],
+                }
+            })
+            .chain(self.calcs.iter().map(|calc| JsonEntity {
+                title: calc.name.clone(),
+                value: calc.expr.eval(json).to_json_value(),
+                pointer: calc.pointer(),
+            }))
+            .collect()
+    }
+
+    fn apply(&mut self, result: Result<Value>) {
+        // A failed poll in between doesn't make a failing rule start again
+        let before = self.checked();
+        let json = match result {
+            Ok(json) => json,
+            Err(e) => {
+                self.error = Some(e.to_string());
+                return;
+            }
+        };
+
+        let columns = self.extract(&json);
+        if let Some(previous) = &self.document {
+            self.changes = diff(previous, &json);
+        }
+        let title = self.title();
+        if let Some(hook) = &mut self.hook {
+            for event in hook.observe(&json, &self.rules, Instant::now()) {
+                hook.run(&title, &event);
+            }
+        }
+        self.document = Some(json);
+
+        let now = Instant::now();
+        for entity in &columns {
+            let same = self
+                .columns
+                .iter()
+                .any(|c| c.pointer == entity.pointer && c.value == entity.value);
+            if !same {
+                self.changed.insert(entity.pointer.clone(), now);
+            }
+        }
+
+        let previous = std::mem::replace(&mut self.columns, columns);
+        if self.history_len > 1 && self.update_source_c > 0 {
+            self.history.push_front(previous);
+            self.history.truncate(self.history_len - 1);
+        }
+        self.error = None;
+        self.last_success = Some(Local::now());
+        let started_failing = self
+            .checked()
+            .iter()
+            .zip(before)
+            .any(|(after, before)| *after == Outcome::Fail && before != Outcome::Fail);
+        if started_failing {
+            self.alert = Some(Instant::now());
+        }
+        self.update_source_c += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use eyre::eyre;
+    use serde_json::json;
+
+    use crate::{args::FeedArgs, decode::InputFormat, feed::Feed, source::Source};
+
+    #[test]
+    fn failed_polls_dont_restart_alerts() {
+        let mut feed = Feed::new(FeedArgs {
+            source: Source::Exec("true".to_string()),
+            label: None,
+            pointers: vec![],
+            formats: vec![],
+            calcs: vec![],
+            rules: vec!["/load < 0.9".parse().unwrap()],
+            on_change: vec![],
+            exec_hook: None,
+            debounce: Duration::from_secs(5),
+            polling_interval: Duration::from_secs(1),
+            history: 1,
+            format: InputFormat::default(),
+            timestamp: None,
+            stale_after: Duration::from_secs(60),
+        });
+
+        feed.apply(Ok(json!({"load": 1.5})));
+        assert!(feed.alert.is_some());
+        feed.acknowledge();
+
+        feed.apply(Err(eyre!("timed out")));
+        assert_eq!(feed.failing(), 0);
+        feed.apply(Ok(json!({"load": 2.5})));
+        assert_eq!(feed.failing(), 1);
+        assert!(feed.alert.is_none());
+    }
+}
@@ -0,0 +1,23 @@
+use std::path::Path;
+
+use eyre::eyre;
+use eyre::Result;
+
+use crate::{
+    compression::decompress,
+    decode::{decode, InputFormat},
+    source::Fetched,
+};
+
+pub async fn read(path: &Path, format: InputFormat) -> Result<Fetched> {
+    let bytes = tokio::fs::read(path)
+        .await
+        .map_err(|e| eyre!("Could not read {}: {}", path.display(), e))?;
+    let bytes = decompress(&bytes)?;
+    let value = decode(&bytes, format.or(InputFormat::from_path(path)))?;
+    Ok(Fetched {
+        value,
+        size: bytes.len(),
+        status: None,
+    })
+}
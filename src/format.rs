@@ -0,0 +1,231 @@
+use std::{fmt, str::FromStr, time::Duration};
+
+use chrono::Local;
+use eyre::{eyre, Result};
+use serde_json::json;
+
+use crate::{json::JsonValue, timestamp};
+
+/// How a column is shown, given after the pointer like `-p /mem/rss:bytes`.
+/// Values that are not numbers are shown as they are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Formatter {
+    /// `1067347099` as `1.07 GB`
+    Bytes,
+    /// `1051200000` as `12d 4h`
+    DurationMs,
+    /// `0.256` as `25.6%`
+    Percent,
+    /// Seconds or milliseconds since the epoch as local time
+    Epoch,
+    /// `3.14159` as `3.14`
+    Fixed2,
+    /// `1067347099` as `1,067,347,099`
+    Thousands,
+}
+
+impl Formatter {
+    pub fn apply(self, value: &JsonValue) -> String {
+        let Some(n) = value.as_f64() else {
+            return value.to_string();
+        };
+        match self {
+            Formatter::Bytes if n >= 0.0 => bytes(n as u64),
+            Formatter::DurationMs if n >= 0.0 => duration(Duration::from_millis(n as u64)),
+            Formatter::Percent => format!("{:.1}%", n * 100.0),
+            Formatter::Epoch => match timestamp::parse(&json!(n)) {
+                Some(time) => time
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string(),
+                None => value.to_string(),
+            },
+            Formatter::Fixed2 => format!("{:.2}", n),
+            Formatter::Thousands => match value {
+                JsonValue::PosInt(i) => thousands(*i),
+                _ => {
+                    let fixed = value.to_string();
+                    match fixed.split_once('.') {
+                        // -0.5 has no sign left once truncated
+                        Some((_, fraction)) => {
+                            let sign = if n < 0.0 { "-" } else { "" };
+                            let whole = thousands(n.abs().trunc() as i64);
+                            format!("{}{}.{}", sign, whole, fraction)
+                        }
+                        None => fixed,
+                    }
+                }
+            },
+            Formatter::Bytes | Formatter::DurationMs => value.to_string(),
+        }
+    }
+
+    /// Splits a trailing `:formatter` off a pointer, a pointer that just
+    /// happens to contain a colon is left as it is.
+    pub fn split(pointer: &str) -> (String, Option<Formatter>) {
+        if let Some((pointer, name)) = pointer.rsplit_once(':') {
+            if let Ok(formatter) = name.parse() {
+                return (pointer.to_string(), Some(formatter));
+            }
+        }
+        (pointer.to_string(), None)
+    }
+
+    /// The formatter given for the pointer or for the object containing it.
+    pub fn find(formats: &[(String, Formatter)], pointer: &str) -> Option<Formatter> {
+        formats
+            .iter()
+            .filter(|(p, _)| {
+                pointer == p
+                    || pointer
+                        .strip_prefix(p.as_str())
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+            .max_by_key(|(p, _)| p.len())
+            .map(|(_, formatter)| *formatter)
+    }
+}
+
+impl FromStr for Formatter {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "bytes" => Ok(Formatter::Bytes),
+            "duration_ms" => Ok(Formatter::DurationMs),
+            "percent" => Ok(Formatter::Percent),
+            "epoch" => Ok(Formatter::Epoch),
+            "fixed2" => Ok(Formatter::Fixed2),
+            "thousands" => Ok(Formatter::Thousands),
+            _ => Err(eyre!("Unknown formatter {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Formatter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Formatter::Bytes => "bytes",
+            Formatter::DurationMs => "duration_ms",
+            Formatter::Percent => "percent",
+            Formatter::Epoch => "epoch",
+            Formatter::Fixed2 => "fixed2",
+            Formatter::Thousands => "thousands",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+fn thousands(n: i64) -> String {
+    let digits = n.unsigned_abs().to_string();
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    match n < 0 {
+        true => format!("-{}", grouped),
+        false => grouped,
+    }
+}
+
+/// Short human readable duration, like `850ms`, `42s`, `3m 12s` or `2h 5m`.
+pub fn duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    match secs {
+        0 => format!("{}ms", d.as_millis()),
+        1..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m {}s", secs / 60, secs % 60),
+        3600..=86399 => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}d {}h", secs / 86400, secs % 86400 / 3600),
+    }
+}
+
+/// Size in decimal units, like `512 B`, `1.20 kB` or `1.07 GB`.
+pub fn bytes(n: u64) -> String {
+    const UNITS: [&str; 5] = ["kB", "MB", "GB", "TB", "PB"];
+    if n < 1000 {
+        return format!("{} B", n);
+    }
+    let mut size = n as f64;
+    let mut unit = "";
+    for u in UNITS {
+        size /= 1000.0;
+        unit = u;
+        if size < 1000.0 {
+            break;
+        }
+    }
+    format!("{:.2} {}", size, unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        format::{bytes, duration, Formatter},
+        json::JsonValue,
+    };
+
+    #[test]
+    fn formatters() {
+        let n = JsonValue::PosInt(1067347099);
+
+        assert_eq!(Formatter::Bytes.apply(&n), "1.07 GB");
+        assert_eq!(Formatter::Thousands.apply(&n), "1,067,347,099");
+        assert_eq!(
+            Formatter::Thousands.apply(&JsonValue::Float(-1234.5)),
+            "-1,234.5"
+        );
+        assert_eq!(Formatter::Thousands.apply(&JsonValue::Float(-0.5)), "-0.5");
+        assert_eq!(
+            Formatter::DurationMs.apply(&JsonValue::PosInt(1051200000)),
+            "12d 4h"
+        );
+        assert_eq!(Formatter::Percent.apply(&JsonValue::Float(0.256)), "25.6%");
+        assert_eq!(Formatter::Fixed2.apply(&JsonValue::Float(0.456)), "0.46");
+        assert_eq!(Formatter::Bytes.apply(&JsonValue::Null), "Null");
+    }
+
+    #[test]
+    fn pointer_with_formatter() {
+        assert_eq!(
+            Formatter::split("/mem/rss:bytes"),
+            ("/mem/rss".to_string(), Some(Formatter::Bytes))
+        );
+        assert_eq!(Formatter::split("/a:b"), ("/a:b".to_string(), None));
+
+        let formats = vec![
+            ("/mem".to_string(), Formatter::Thousands),
+            ("/mem/rss".to_string(), Formatter::Bytes),
+        ];
+        assert_eq!(
+            Formatter::find(&formats, "/mem/rss"),
+            Some(Formatter::Bytes)
+        );
+        assert_eq!(
+            Formatter::find(&formats, "/mem/heap"),
+            Some(Formatter::Thousands)
+        );
+        assert_eq!(Formatter::find(&formats, "/memory"), None);
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(bytes(512), "512 B");
+        assert_eq!(bytes(1200), "1.20 kB");
+        assert_eq!(bytes(1067347099), "1.07 GB");
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(duration(Duration::from_millis(850)), "850ms");
+        assert_eq!(duration(Duration::from_secs(42)), "42s");
+        assert_eq!(duration(Duration::from_secs(192)), "3m 12s");
+        assert_eq!(duration(Duration::from_secs(7500)), "2h 5m");
+        assert_eq!(duration(Duration::from_secs(90000)), "1d 1h");
+    }
+}
@@ -0,0 +1,292 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    process::Stdio,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde_json::Value;
+
+use crate::{
+    exec::shell,
+    expr::{Outcome, Rule},
+};
+
+/// What set the hook off.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Trigger {
+    /// The value at a watched pointer changed.
+    Change(String),
+    /// An assertion started failing.
+    Assert(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub trigger: Trigger,
+    pub old: String,
+    pub new: String,
+}
+
+/// Last reported value of a pointer or outcome of a rule.
+struct Watch {
+    reported: String,
+    /// Latest value, reported once the debounce is up.
+    seen: String,
+    fired: Option<Instant>,
+    /// Whether a change to the value runs the hook.
+    fires: fn(&str) -> bool,
+}
+
+impl Watch {
+    fn new(value: &str, fires: fn(&str) -> bool) -> Self {
+        Self {
+            reported: value.to_string(),
+            seen: value.to_string(),
+            fired: None,
+            fires,
+        }
+    }
+
+    fn update(&mut self, new: &str, now: Instant, debounce: Duration) -> Option<String> {
+        self.seen = new.to_string();
+        self.settle(now, debounce)
+    }
+
+    /// Takes the latest value once the last firing is `debounce` ago,
+    /// returns the value reported before if the change fires.
+    fn settle(&mut self, now: Instant, debounce: Duration) -> Option<String> {
+        let waiting = self.fired.is_some_and(|fired| now - fired < debounce);
+        if self.reported == self.seen || waiting {
+            return None;
+        }
+        let old = std::mem::replace(&mut self.reported, self.seen.clone());
+        if !(self.fires)(&self.seen) {
+            return None;
+        }
+        self.fired = Some(now);
+        Some(old)
+    }
+}
+
+/// A command run when a watched value changes or an assertion starts
+/// failing, `--on-change /status/state --exec-hook notify.sh`.
+///
+/// Every pointer and rule fires at most once per `debounce`, changes in
+/// between are compared with what was last reported when it is up, so a
+/// value that flaps back doesn't fire at all.
+pub struct Hook {
+    command: String,
+    pointers: Vec<String>,
+    debounce: Duration,
+    watches: HashMap<Trigger, Watch>,
+    /// Why the command failed the last time it was run.
+    error: Arc<Mutex<Option<String>>>,
+}
+
+impl Hook {
+    pub fn new(command: String, pointers: Vec<String>, debounce: Duration) -> Self {
+        Self {
+            command,
+            pointers,
+            debounce,
+            watches: HashMap::new(),
+            error: Arc::default(),
+        }
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().expect("hook error").clone()
+    }
+
+    /// Events for a new document. Values seen in the first document are
+    /// only remembered, a rule failing right away fires.
+    pub fn observe(&mut self, document: &Value, rules: &[Rule], now: Instant) -> Vec<Event> {
+        let mut events = vec![];
+        for pointer in &self.pointers {
+            let new = text(document.pointer(pointer));
+            let trigger = Trigger::Change(pointer.clone());
+            match self.watches.entry(trigger.clone()) {
+                Entry::Vacant(entry) => {
+                    entry.insert(Watch::new(&new, |_| true));
+                }
+                Entry::Occupied(entry) => {
+                    if let Some(old) = entry.into_mut().update(&new, now, self.debounce) {
+                        events.push(Event { trigger, old, new });
+                    }
+                }
+            }
+        }
+        for rule in rules {
+            let new = match rule.check(document) {
+                Outcome::Pass => "pass",
+                Outcome::Fail => "fail",
+                Outcome::Unknown => "unknown",
+            };
+            let trigger = Trigger::Assert(rule.text.clone());
+            let watch = self
+                .watches
+                .entry(trigger.clone())
+                .or_insert_with(|| Watch::new("unknown", |new| new == "fail"));
+            if let Some(old) = watch.update(new, now, self.debounce) {
+                events.push(Event {
+                    trigger,
+                    old,
+                    new: new.to_string(),
+                });
+            }
+        }
+        events
+    }
+
+    /// Events for changes held back by the debounce that is up by now, so
+    /// they fire without waiting for another document.
+    pub fn due(&mut self, now: Instant) -> Vec<Event> {
+        let mut events = vec![];
+        for (trigger, watch) in &mut self.watches {
+            if let Some(old) = watch.settle(now, self.debounce) {
+                events.push(Event {
+                    trigger: trigger.clone(),
+                    old,
+                    new: watch.reported.clone(),
+                });
+            }
+        }
+        events
+    }
+
+    /// Starts the command without waiting for it, its output is dropped so
+    /// it doesn't end up in the table. Only why it failed is kept.
+    pub fn run(&self, source: &str, event: &Event) {
+        let mut command = shell(&self.command);
+        command
+            .env("TJSON_SOURCE", source)
+            .env("TJSON_OLD", &event.old)
+            .env("TJSON_NEW", &event.new)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+        match &event.trigger {
+            Trigger::Change(pointer) => command
+                .env("TJSON_EVENT", "change")
+                .env("TJSON_POINTER", pointer),
+            Trigger::Assert(rule) => command.env("TJSON_EVENT", "assert").env("TJSON_RULE", rule),
+        };
+        match command.spawn() {
+            Ok(child) => {
+                let error = self.error.clone();
+                tokio::spawn(async move {
+                    let result = match child.wait_with_output().await {
+                        Ok(output) if output.status.success() => None,
+                        Ok(output) => Some(format!(
+                            "({}) {}",
+                            output.status,
+                            String::from_utf8_lossy(&output.stderr).trim()
+                        )),
+                        Err(e) => Some(e.to_string()),
+                    };
+                    *error.lock().expect("hook error") = result;
+                });
+            }
+            Err(e) => *self.error.lock().expect("hook error") = Some(e.to_string()),
+        }
+    }
+}
+
+/// Strings are passed as they are, anything else as json and missing
+/// values as an empty string.
+fn text(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(s)) => s.clone(),
+        Some(value) => value.to_string(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use serde_json::json;
+
+    use crate::hook::{Event, Hook, Trigger};
+
+    #[test]
+    fn changes_are_debounced() {
+        let mut hook = Hook::new(
+            "true".to_string(),
+            vec!["/state".to_string()],
+            Duration::from_secs(10),
+        );
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert!(hook.observe(&json!({"state": "ok"}), &[], at(0)).is_empty());
+        assert_eq!(
+            hook.observe(&json!({"state": "failed"}), &[], at(1)),
+            vec![Event {
+                trigger: Trigger::Change("/state".to_string()),
+                old: "ok".to_string(),
+                new: "failed".to_string(),
+            }]
+        );
+        assert!(hook.observe(&json!({"state": "ok"}), &[], at(2)).is_empty());
+        assert!(hook
+            .observe(&json!({"state": "failed"}), &[], at(3))
+            .is_empty());
+        assert!(hook
+            .observe(&json!({"state": "failed"}), &[], at(12))
+            .is_empty());
+        assert_eq!(hook.observe(&json!({"state": 3}), &[], at(13))[0].new, "3");
+    }
+
+    #[test]
+    fn held_back_changes_fire_when_the_debounce_is_up() {
+        let mut hook = Hook::new(
+            "true".to_string(),
+            vec!["/state".to_string()],
+            Duration::from_secs(10),
+        );
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        hook.observe(&json!({"state": "ok"}), &[], at(0));
+        assert_eq!(
+            hook.observe(&json!({"state": "failed"}), &[], at(1)).len(),
+            1
+        );
+        assert!(hook.observe(&json!({"state": "ok"}), &[], at(2)).is_empty());
+        assert!(hook.due(at(5)).is_empty());
+        assert_eq!(
+            hook.due(at(11)),
+            vec![Event {
+                trigger: Trigger::Change("/state".to_string()),
+                old: "failed".to_string(),
+                new: "ok".to_string(),
+            }]
+        );
+        assert!(hook.due(at(30)).is_empty());
+    }
+
+    #[test]
+    fn failing_rules_fire() {
+        let mut hook = Hook::new("true".to_string(), vec![], Duration::ZERO);
+        let rules = ["/load < 0.9".parse().unwrap()];
+        let now = Instant::now();
+
+        assert!(hook.observe(&json!({"load": 0.5}), &rules, now).is_empty());
+        let events = hook.observe(&json!({"load": 1.5}), &rules, now);
+        assert_eq!(
+            events[0].trigger,
+            Trigger::Assert("/load < 0.9".to_string())
+        );
+        assert_eq!(
+            (events[0].old.as_str(), events[0].new.as_str()),
+            ("pass", "fail")
+        );
+        assert!(hook.observe(&json!({"load": 2.5}), &rules, now).is_empty());
+        assert!(hook.observe(&json!({"load": 0.5}), &rules, now).is_empty());
+        assert_eq!(hook.observe(&json!({}), &rules, now).len(), 0);
+        assert_eq!(hook.observe(&json!({"load": 1.5}), &rules, now).len(), 1);
+    }
+}
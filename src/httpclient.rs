@@ -1,11 +1,55 @@
-use eyre::eyre;
+use std::path::Path;
+
 use eyre::Result;
-use serde_json::Value;
+use lazy_static::lazy_static;
+use reqwest::header::{ACCEPT_ENCODING, CONTENT_TYPE};
+
+use crate::{
+    compression::{self, decompress},
+    decode::{decode, InputFormat},
+    source::{self, Fetched, ResponseError},
+};
+
+lazy_static! {
+    static ref CLIENT: reqwest::Client = reqwest::Client::builder()
+        .timeout(source::TIMEOUT)
+        .build()
+        .expect("http client");
+}
+
+pub async fn fetch(url: &str, format: InputFormat) -> Result<Fetched> {
+    let response = CLIENT
+        .get(url)
+        .header(ACCEPT_ENCODING, compression::ACCEPT_ENCODING)
+        .send()
+        .await?;
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(InputFormat::from_content_type);
+    let format = format
+        .or(content_type)
+        .or(InputFormat::from_path(Path::new(response.url().path())));
 
-pub async fn fetch(url: &str) -> Result<Value> {
-    let body = reqwest::get(url).await?.text().await?;
-    match serde_json::from_str(&body) {
-        Ok(v) => Ok(v),
-        Err(e) => Err(eyre!("Could not fetch data: {}", e)),
+    let status = response.status();
+    let body = response.bytes().await?;
+    let body = decompress(&body)?;
+    let rejected = |message: String| ResponseError {
+        status: status.as_u16(),
+        size: body.len(),
+        message,
+    };
+    // Error pages are not decoded, they are rarely the expected format
+    if !status.is_success() {
+        return Err(rejected(format!("HTTP {}", status)).into());
+    }
+    match decode(&body, format) {
+        Ok(value) => Ok(Fetched {
+            value,
+            size: body.len(),
+            status: Some(status.as_u16()),
+        }),
+        Err(e) => Err(rejected(e.to_string()).into()),
     }
 }
@@ -1,123 +1,173 @@
-pub mod json {
-    use serde_json::Number;
-    use serde_json::Value;
+use std::{cmp::Ordering, fmt};
+
+use serde_json::Number;
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum JsonValue {
+    PosInt(i64),
+    Float(f64),
+    Text(String),
+    Boolean(bool),
+    Null,
+}
 
-    #[derive(Debug, PartialEq, PartialOrd)]
-    pub enum JsonValue {
-        PosInt(i64),
-        Float(f64),
-        Text(String),
-        Boolean(bool),
-        Null,
+impl fmt::Display for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonValue::PosInt(v) => write!(f, "{}", v),
+            JsonValue::Float(v) => write!(f, "{}", v),
+            JsonValue::Text(v) => write!(f, "{}", v),
+            JsonValue::Boolean(v) => write!(f, "{}", v),
+            JsonValue::Null => write!(f, "Null"),
+        }
     }
+}
 
-    impl ToString for JsonValue {
-        fn to_string(&self) -> String {
-            match self {
-                JsonValue::PosInt(v) => v.to_string(),
-                JsonValue::Float(v) => v.to_string(),
-                JsonValue::Text(v) => v.to_string(),
-                JsonValue::Boolean(v) => v.to_string(),
-                JsonValue::Null => "Null".to_string(),
-            }
+impl JsonValue {
+    /// Orders numbers by value whether they are integers or floats,
+    /// then text, booleans and nulls.
+    pub fn compare(&self, other: &JsonValue) -> Ordering {
+        match (self.as_f64(), other.as_f64()) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            _ => match (self, other) {
+                (JsonValue::Text(a), JsonValue::Text(b)) => a.cmp(b),
+                (JsonValue::Boolean(a), JsonValue::Boolean(b)) => a.cmp(b),
+                _ => self.rank().cmp(&other.rank()),
+            },
         }
     }
 
-    pub type Title = String;
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::PosInt(v) => Some(*v as f64),
+            JsonValue::Float(v) => Some(*v),
+            _ => None,
+        }
+    }
 
-    #[derive(Debug, PartialEq, PartialOrd)]
-    pub struct JsonEntity {
-        pub title: Title,
-        pub value: JsonValue,
+    fn rank(&self) -> u8 {
+        match self {
+            JsonValue::PosInt(_) | JsonValue::Float(_) => 0,
+            JsonValue::Text(_) => 1,
+            JsonValue::Boolean(_) => 2,
+            JsonValue::Null => 3,
+        }
     }
 
-    #[derive(Debug, PartialEq, PartialOrd)]
-    pub enum EntityResult {
-        Entities(Vec<JsonEntity>),
-        Entity(JsonEntity),
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            JsonValue::PosInt(_) => "integer",
+            JsonValue::Float(_) => "float",
+            JsonValue::Text(_) => "string",
+            JsonValue::Boolean(_) => "boolean",
+            JsonValue::Null => "null",
+        }
     }
+}
 
-    pub fn get_cell(json_object: &Value, pointer: &str) -> Option<EntityResult> {
-        let title = get_field_name(pointer);
+pub type Title = String;
 
-        if title.is_none() {
-            return None;
-        }
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct JsonEntity {
+    pub title: Title,
+    pub value: JsonValue,
+    pub pointer: String,
+}
+
+#[derive(Debug, PartialEq, PartialOrd)]
+pub enum EntityResult {
+    Entities(Vec<JsonEntity>),
+    Entity(JsonEntity),
+}
 
-        let t = title.unwrap().to_string();
-
-        match json_object.pointer(pointer) {
-            Some(v) => match v {
-                Value::Null => Some(EntityResult::Entity(JsonEntity {
-                    title: t,
-                    value: JsonValue::Null,
-                })),
-
-                Value::Bool(b) => Some(EntityResult::Entity(JsonEntity {
-                    title: t,
-                    value: JsonValue::Boolean(b.clone()),
-                })),
-                Value::Number(n) => Some(EntityResult::Entity(JsonEntity {
-                    title: t,
-                    value: to_value(n),
-                })),
-                Value::String(s) => Some(EntityResult::Entity(JsonEntity {
-                    title: t,
-                    value: JsonValue::Text(s.clone()),
-                })),
-                // Todo: return list of cells with only primitives
-                Value::Array(_) => None,
-                Value::Object(v) => {
-                    let mut result = Vec::new();
-                    for (key, value) in v.iter() {
-                        let n: Option<JsonEntity> = match value {
-                            Value::Null => None,
-                            Value::Bool(t) => Some(JsonEntity {
-                                title: key.to_owned(),
-                                value: JsonValue::Boolean(t.clone()),
-                            }),
-                            Value::Number(n) => Some(JsonEntity {
-                                title: key.to_owned(),
-                                value: to_value(n),
-                            }),
-                            Value::String(s) => Some(JsonEntity {
-                                title: key.to_owned(),
-                                value: JsonValue::Text(s.clone()),
-                            }),
-                            Value::Array(_) => None,
-                            Value::Object(_) => None,
-                        };
-                        match n {
-                            Some(v) => result.push(v),
-                            None => {}
-                        }
+pub fn get_cell(json_object: &Value, pointer: &str) -> Option<EntityResult> {
+    let t = get_field_name(pointer)?.to_string();
+
+    match json_object.pointer(pointer) {
+        Some(v) => match v {
+            Value::Null => Some(EntityResult::Entity(JsonEntity {
+                title: t,
+                value: JsonValue::Null,
+                pointer: pointer.to_string(),
+            })),
+
+            Value::Bool(b) => Some(EntityResult::Entity(JsonEntity {
+                title: t,
+                value: JsonValue::Boolean(*b),
+                pointer: pointer.to_string(),
+            })),
+            Value::Number(n) => Some(EntityResult::Entity(JsonEntity {
+                title: t,
+                value: to_value(n),
+                pointer: pointer.to_string(),
+            })),
+            Value::String(s) => Some(EntityResult::Entity(JsonEntity {
+                title: t,
+                value: JsonValue::Text(s.clone()),
+                pointer: pointer.to_string(),
+            })),
+            // Todo: return list of cells with only primitives
+            Value::Array(_) => None,
+            Value::Object(v) => {
+                let mut result = Vec::new();
+                for (key, value) in v.iter() {
+                    let child = format!("{}/{}", pointer, escape_token(key));
+                    let n: Option<JsonEntity> = match value {
+                        Value::Null => None,
+                        Value::Bool(t) => Some(JsonEntity {
+                            title: key.to_owned(),
+                            value: JsonValue::Boolean(*t),
+                            pointer: child.clone(),
+                        }),
+                        Value::Number(n) => Some(JsonEntity {
+                            title: key.to_owned(),
+                            value: to_value(n),
+                            pointer: child.clone(),
+                        }),
+                        Value::String(s) => Some(JsonEntity {
+                            title: key.to_owned(),
+                            value: JsonValue::Text(s.clone()),
+                            pointer: child.clone(),
+                        }),
+                        Value::Array(_) => None,
+                        Value::Object(_) => None,
+                    };
+                    if let Some(v) = n {
+                        result.push(v);
                     }
-                    Some(EntityResult::Entities(result))
                 }
-            },
-            None => None,
-        }
+                Some(EntityResult::Entities(result))
+            }
+        },
+        None => None,
     }
+}
 
-    fn to_value(number: &Number) -> JsonValue {
-        if number.is_i64() || number.is_u64() {
-            JsonValue::PosInt(number.as_i64().expect("Invalid number"))
-        } else {
-            JsonValue::Float(number.as_f64().expect("Invalid number"))
-        }
+fn to_value(number: &Number) -> JsonValue {
+    if number.is_i64() || number.is_u64() {
+        JsonValue::PosInt(number.as_i64().expect("Invalid number"))
+    } else {
+        JsonValue::Float(number.as_f64().expect("Invalid number"))
     }
+}
 
-    fn get_field_name(point: &str) -> Option<&str> {
-        point.split('/').last()
-    }
+/// Escapes a key for use as a json pointer reference token.
+pub fn escape_token(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn get_field_name(point: &str) -> Option<&str> {
+    point.split('/').next_back()
 }
 
 #[cfg(test)]
+#[allow(clippy::approx_constant)]
 mod tests {
 
     use serde_json::Value;
 
-    use crate::json::json::{get_cell, EntityResult, JsonEntity, JsonValue};
+    use crate::json::{get_cell, EntityResult, JsonEntity, JsonValue};
     static JSON_STR: &str = "{ \"status\": { \"state\": \"running\", \"done\": 1234, \"float\": 3.14, \"negafloat\": -3.14, \"negative\": -123 } }";
 
     #[test]
@@ -129,7 +179,8 @@ mod tests {
             str,
             Some(EntityResult::Entity(JsonEntity {
                 title: String::from("state"),
-                value: JsonValue::Text("running".to_string())
+                value: JsonValue::Text("running".to_string()),
+                pointer: String::from("/status/state")
             }))
         );
     }
@@ -143,7 +194,8 @@ mod tests {
             posint,
             Some(EntityResult::Entity(JsonEntity {
                 title: String::from("done"),
-                value: JsonValue::PosInt(1234)
+                value: JsonValue::PosInt(1234),
+                pointer: String::from("/status/done")
             }))
         );
     }
@@ -157,7 +209,8 @@ mod tests {
             floatpos,
             Some(EntityResult::Entity(JsonEntity {
                 title: String::from("float"),
-                value: JsonValue::Float(3.14)
+                value: JsonValue::Float(3.14),
+                pointer: String::from("/status/float")
             }))
         );
     }
@@ -171,7 +224,8 @@ mod tests {
             negafloat,
             Some(EntityResult::Entity(JsonEntity {
                 title: String::from("negafloat"),
-                value: JsonValue::Float(-3.14)
+                value: JsonValue::Float(-3.14),
+                pointer: String::from("/status/negafloat")
             }))
         );
     }
@@ -185,7 +239,8 @@ mod tests {
             negative,
             Some(EntityResult::Entity(JsonEntity {
                 title: String::from("negative"),
-                value: JsonValue::PosInt(-123)
+                value: JsonValue::PosInt(-123),
+                pointer: String::from("/status/negative")
             }))
         );
     }
@@ -0,0 +1,356 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use eyre::{eyre, Result};
+use serde::Deserialize;
+
+/// Everything a key can be bound to. What an action does depends on what is
+/// shown, `Up` moves the table selection, the explorer or the raw document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Quit,
+    Help,
+    Refresh,
+    Pause,
+    Acknowledge,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Top,
+    Bottom,
+    Left,
+    Right,
+    Select,
+    Back,
+    NextView,
+    PreviousView,
+    NextSource,
+    PreviousSource,
+    Explorer,
+    Diff,
+    Raw,
+    Transpose,
+    Sort,
+    ClearSort,
+    Filter,
+    NextMatch,
+    PreviousMatch,
+    TogglePointer,
+}
+
+impl Action {
+    pub const ALL: [Action; 29] = [
+        Action::Quit,
+        Action::Help,
+        Action::Refresh,
+        Action::Pause,
+        Action::Acknowledge,
+        Action::Up,
+        Action::Down,
+        Action::PageUp,
+        Action::PageDown,
+        Action::Top,
+        Action::Bottom,
+        Action::Left,
+        Action::Right,
+        Action::Select,
+        Action::Back,
+        Action::NextView,
+        Action::PreviousView,
+        Action::NextSource,
+        Action::PreviousSource,
+        Action::Explorer,
+        Action::Diff,
+        Action::Raw,
+        Action::Transpose,
+        Action::Sort,
+        Action::ClearSort,
+        Action::Filter,
+        Action::NextMatch,
+        Action::PreviousMatch,
+        Action::TogglePointer,
+    ];
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["q"],
+            Action::Help => &["?"],
+            Action::Refresh => &["R", "f5"],
+            Action::Pause => &["p"],
+            Action::Acknowledge => &["a"],
+            Action::Up => &["k", "up"],
+            Action::Down => &["j", "down"],
+            Action::PageUp => &["pageup"],
+            Action::PageDown => &["pagedown"],
+            Action::Top => &["g", "home"],
+            Action::Bottom => &["G", "end"],
+            Action::Left => &["h", "left"],
+            Action::Right => &["l", "right"],
+            Action::Select => &["enter"],
+            Action::Back => &["esc"],
+            Action::NextView => &["tab"],
+            Action::PreviousView => &["shift-tab"],
+            Action::NextSource => &["f"],
+            Action::PreviousSource => &["F"],
+            Action::Explorer => &["e"],
+            Action::Diff => &["d"],
+            Action::Raw => &["r"],
+            Action::Transpose => &["t"],
+            Action::Sort => &["s"],
+            Action::ClearSort => &["S"],
+            Action::Filter => &["/"],
+            Action::NextMatch => &["n"],
+            Action::PreviousMatch => &["N"],
+            Action::TogglePointer => &["space"],
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Help => "show this help",
+            Action::Refresh => "poll every source now",
+            Action::Pause => "pause or resume polling",
+            Action::Acknowledge => "acknowledge failing assertions, stop flashing",
+            Action::Up => "move up",
+            Action::Down => "move down",
+            Action::PageUp => "move a page up",
+            Action::PageDown => "move a page down",
+            Action::Top => "go to the first row or line",
+            Action::Bottom => "go to the last row or line",
+            Action::Left => "select the column to the left, collapse in the explorer",
+            Action::Right => "select the column to the right, expand in the explorer",
+            Action::Select => "show the selected cell, toggle a node in the explorer",
+            Action::Back => "close the popup, explorer or changes",
+            Action::NextView => "next view",
+            Action::PreviousView => "previous view",
+            Action::NextSource => "focus the next source",
+            Action::PreviousSource => "focus the previous source",
+            Action::Explorer => "explore the document",
+            Action::Diff => "show what changed",
+            Action::Raw => "show the raw document, right, bottom or hidden",
+            Action::Transpose => "show a row per field",
+            Action::Sort => "sort by the selected column, again for descending",
+            Action::ClearSort => "stop sorting",
+            Action::Filter => "filter rows, search the raw document",
+            Action::NextMatch => "next match in the raw document",
+            Action::PreviousMatch => "previous match in the raw document",
+            Action::TogglePointer => "add or remove the selected node as a column",
+        }
+    }
+}
+
+/// The name used in the config file, like `next_view`.
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, c) in format!("{:?}", self).chars().enumerate() {
+            match c.is_ascii_uppercase() {
+                true if i > 0 => write!(f, "_{}", c.to_ascii_lowercase())?,
+                true => write!(f, "{}", c.to_ascii_lowercase())?,
+                false => write!(f, "{}", c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A key with the modifiers that have to be held, like `ctrl-c`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    /// Shift is part of the character or of `BackTab`, so only control and
+    /// alt are compared.
+    fn matches(&self, event: &KeyEvent) -> bool {
+        let held = event.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+        self.code == event.code && self.modifiers == held
+    }
+}
+
+impl FromStr for Key {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut name = s;
+        loop {
+            if let Some(rest) = name.strip_prefix("ctrl-") {
+                modifiers |= KeyModifiers::CONTROL;
+                name = rest;
+            } else if let Some(rest) = name.strip_prefix("alt-") {
+                modifiers |= KeyModifiers::ALT;
+                name = rest;
+            } else {
+                break;
+            }
+        }
+
+        let mut chars = name.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match name {
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "shift-tab" | "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "space" => KeyCode::Char(' '),
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                _ => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(eyre!("Unknown key {}", s)),
+                },
+            },
+        };
+        Ok(Key { code, modifiers })
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            KeyCode::BackTab => write!(f, "shift-tab"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            code => write!(f, "{}", format!("{:?}", code).to_lowercase()),
+        }
+    }
+}
+
+/// Which action every key is bound to. Actions given in the config file
+/// replace all of their default keys and take their keys away from the
+/// defaults of other actions.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(Key, Action)>,
+}
+
+impl Keymap {
+    pub fn new(overrides: &HashMap<Action, Vec<String>>) -> Result<Self> {
+        let mut bindings: Vec<(Key, Action)> = vec![];
+        // In the order of `Action::ALL` so errors don't depend on the map
+        for action in Action::ALL {
+            for key in overrides.get(&action).into_iter().flatten() {
+                let key: Key = key.parse()?;
+                if let Some((_, other)) = bindings.iter().find(|(k, _)| *k == key) {
+                    return Err(eyre!(
+                        "Key {} is bound to both {} and {}",
+                        key,
+                        other,
+                        action
+                    ));
+                }
+                bindings.push((key, action));
+            }
+        }
+        let overridden = bindings.len();
+        for action in Action::ALL {
+            if !overrides.contains_key(&action) {
+                for key in action.default_keys() {
+                    let key: Key = key.parse()?;
+                    if !bindings[..overridden].iter().any(|(k, _)| *k == key) {
+                        bindings.push((key, action));
+                    }
+                }
+            }
+        }
+        Ok(Self { bindings })
+    }
+
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(key, _)| key.matches(event))
+            .map(|(_, action)| *action)
+    }
+
+    /// Keys bound to the action, in the order they were given.
+    pub fn keys(&self, action: Action) -> Vec<Key> {
+        self.bindings
+            .iter()
+            .filter(|(_, a)| *a == action)
+            .map(|(key, _)| *key)
+            .collect()
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(&HashMap::new()).expect("default keys")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use crate::keymap::{Action, Key, Keymap};
+
+    #[test]
+    fn parse_and_show_keys() {
+        for key in [
+            "q",
+            "G",
+            "ctrl-c",
+            "alt-enter",
+            "shift-tab",
+            "f5",
+            "space",
+            "pageup",
+        ] {
+            let parsed: Key = key.parse().unwrap();
+            assert_eq!(parsed.to_string().to_lowercase(), key.to_lowercase());
+        }
+        assert!("hyper-x".parse::<Key>().is_err());
+    }
+
+    #[test]
+    fn overrides_replace_defaults() {
+        let overrides = HashMap::from([(Action::Up, vec!["j".to_string(), "ctrl-p".to_string()])]);
+        let keymap = Keymap::new(&overrides).unwrap();
+
+        let j = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE);
+        let k = KeyEvent::new(KeyCode::Char('k'), KeyModifiers::NONE);
+        let ctrl_p = KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL);
+        let shifted = KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT);
+
+        assert_eq!(keymap.action(&j), Some(Action::Up));
+        assert_eq!(keymap.action(&k), None);
+        assert_eq!(keymap.action(&ctrl_p), Some(Action::Up));
+        assert_eq!(keymap.action(&shifted), Some(Action::Bottom));
+        // j went to up, so down only keeps its arrow key
+        assert_eq!(keymap.keys(Action::Down).len(), 1);
+
+        let overrides = HashMap::from([
+            (Action::Up, vec!["x".to_string()]),
+            (Action::NextView, vec!["x".to_string()]),
+        ]);
+        let error = Keymap::new(&overrides).unwrap_err();
+        assert_eq!(error.to_string(), "Key x is bound to both up and next_view");
+    }
+}
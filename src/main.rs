@@ -1,20 +1,59 @@
 mod args;
+mod check;
+mod compression;
+mod config;
+mod decode;
+mod diff;
+mod exec;
+mod explorer;
+mod expr;
+mod feed;
+mod file;
+mod format;
+mod hook;
 mod httpclient;
 mod json;
+mod keymap;
+mod prompt;
+mod raw;
+mod source;
+mod sse;
+mod table;
+mod tail;
+mod timestamp;
 mod tui;
 mod ui;
+mod view;
+mod ws;
 
-use clap::Parser;
+use std::{process::ExitCode, time::Duration};
+
+use clap::{CommandFactory, FromArgMatches};
 use color_eyre::Result;
 
-use crate::{args::args::TJsonArgs, ui::ui::App};
+use crate::{args::TJsonArgs, config::Config, keymap::Keymap, ui::App};
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    let args = TJsonArgs::parse();
-    let mut app = App::new(args);
+async fn main() -> Result<ExitCode> {
+    let matches = TJsonArgs::command().get_matches();
+    let args = TJsonArgs::from_arg_matches(&matches)?;
+    if args.check {
+        // Returned rather than exited so polls still running are dropped
+        let code = check::run(args.views(&matches)?, Duration::from_secs(args.timeout)).await;
+        return Ok(ExitCode::from(code));
+    }
+    let config = Config::load(args.config.as_deref())?;
+    let keymap = Keymap::new(&config.keys)?;
+    let mut app = App::new(
+        args.views(&matches)?,
+        args.layout,
+        args.poll_hidden,
+        args.freeze_column,
+        args.alert,
+        keymap,
+    );
     app.run().await?;
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
 /*
     let mut std = stdin().lock();
@@ -28,7 +67,7 @@ async fn main() -> Result<()> {
         .iter()
         .map(|pointer| get_cell(&jo, &pointer))
         .filter_map(|f| f)
-        .flat_map(|v| -> Vec<json::json::JsonEntity> {
+        .flat_map(|v| -> Vec<json::JsonEntity> {
             match v {
                 EntityResult::Entities(cs) => cs,
                 EntityResult::Entity(c) => vec![c],
@@ -0,0 +1,45 @@
+use crossterm::event::{KeyCode, KeyEvent};
+
+/// What a key did to a prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PromptEvent {
+    Edited,
+    Submitted(String),
+    Cancelled,
+}
+
+/// Single line text input shown at the bottom of the screen.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Prompt {
+    pub label: String,
+    pub input: String,
+}
+
+impl Prompt {
+    pub fn new(label: &str, input: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            input: input.to_string(),
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> PromptEvent {
+        match key.code {
+            KeyCode::Enter => PromptEvent::Submitted(self.input.clone()),
+            KeyCode::Esc => PromptEvent::Cancelled,
+            KeyCode::Backspace => {
+                self.input.pop();
+                PromptEvent::Edited
+            }
+            KeyCode::Char(c) => {
+                self.input.push(c);
+                PromptEvent::Edited
+            }
+            _ => PromptEvent::Edited,
+        }
+    }
+
+    pub fn line(&self) -> String {
+        format!("{}{}", self.label, self.input)
+    }
+}
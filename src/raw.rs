@@ -0,0 +1,282 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawPosition {
+    Right,
+    Bottom,
+}
+
+/// Pretty printed, colourised view of the last fetched document. The
+/// search and scroll position are kept while it is hidden.
+#[derive(Debug, Default)]
+pub struct RawPane {
+    /// Where the pane is shown, if it is.
+    pub position: Option<RawPosition>,
+    scroll: usize,
+    search: Option<String>,
+    current: usize,
+    cache: Option<Cache>,
+}
+
+/// The last document shown, its lines and the lines matching the search,
+/// so they are only rebuilt when the document or the search changes.
+#[derive(Debug)]
+struct Cache {
+    document: Value,
+    lines: Vec<Line<'static>>,
+    search: Option<String>,
+    matches: Vec<usize>,
+}
+
+impl RawPane {
+    /// Shows the pane to the right, then at the bottom, then hides it.
+    pub fn toggle(&mut self) {
+        self.position = match self.position {
+            None => Some(RawPosition::Right),
+            Some(RawPosition::Right) => Some(RawPosition::Bottom),
+            Some(RawPosition::Bottom) => None,
+        };
+    }
+
+    pub fn is_shown(&self) -> bool {
+        self.position.is_some()
+    }
+
+    pub fn search(&self) -> Option<&str> {
+        self.search.as_deref()
+    }
+
+    pub fn scroll_by(&mut self, delta: isize) {
+        self.scroll = self.scroll.saturating_add_signed(delta);
+    }
+
+    pub fn scroll_to(&mut self, line: usize) {
+        self.scroll = line;
+    }
+
+    /// Searches case-insensitively and jumps to the first match.
+    pub fn set_search(&mut self, document: &Value, search: &str) {
+        self.search = match search.is_empty() {
+            true => None,
+            false => Some(search.to_string()),
+        };
+        self.current = 0;
+        self.jump(document, 0);
+    }
+
+    /// Moves to the next or previous match, wrapping around.
+    pub fn jump(&mut self, document: &Value, delta: isize) {
+        self.refresh(document);
+        let Some(Cache { matches, .. }) = &self.cache else {
+            return;
+        };
+        if matches.is_empty() {
+            return;
+        }
+        let n = matches.len() as isize;
+        let current = ((self.current as isize + delta).rem_euclid(n)) as usize;
+        self.scroll = matches[current];
+        self.current = current;
+    }
+
+    /// Brings the cache up to date with the document and the search. The
+    /// current match is kept within the matches whenever they change.
+    fn refresh(&mut self, document: &Value) {
+        let changed = !matches!(&self.cache, Some(cache) if &cache.document == document);
+        let cache = match changed {
+            true => self.cache.insert(Cache {
+                document: document.clone(),
+                lines: lines(document),
+                search: None,
+                matches: vec![],
+            }),
+            false => self.cache.as_mut().expect("cache of the same document"),
+        };
+        if changed || cache.search != self.search {
+            cache.search = self.search.clone();
+            cache.matches = matches(&cache.lines, self.search.as_deref());
+            self.current = self.current.min(cache.matches.len().saturating_sub(1));
+        }
+    }
+
+    pub fn render(&mut self, f: &mut Frame, area: Rect, document: Option<&Value>) {
+        if let Some(document) = document {
+            self.refresh(document);
+        }
+        let (lines, matches) = match (document, &self.cache) {
+            (Some(_), Some(cache)) => (cache.lines.as_slice(), cache.matches.as_slice()),
+            _ => (&[][..], &[][..]),
+        };
+
+        self.scroll = self.scroll.min(lines.len().saturating_sub(1));
+        let title = match (&self.search, matches.len()) {
+            (Some(search), 0) => format!("Raw /{} (no matches)", search),
+            (Some(search), n) => format!("Raw /{} ({}/{})", search, self.current + 1, n),
+            (None, _) => "Raw".to_string(),
+        };
+
+        // Only the visible lines are styled and handed to the Paragraph,
+        // which can't scroll further than a u16 anyway
+        let end = lines.len().min(self.scroll + area.height as usize);
+        let mut visible = lines[self.scroll.min(end)..end].to_vec();
+        for (n, i) in matches.iter().enumerate() {
+            let Some(line) = i.checked_sub(self.scroll).and_then(|i| visible.get_mut(i)) else {
+                continue;
+            };
+            let style = match n == self.current {
+                true => Style::default().bg(Color::Yellow).fg(Color::Black),
+                false => Style::default().add_modifier(Modifier::UNDERLINED),
+            };
+            line.patch_style(style);
+        }
+        let p = Paragraph::new(visible).block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(p, area);
+    }
+}
+
+/// Lines containing the search, ignoring case.
+fn matches(lines: &[Line], search: Option<&str>) -> Vec<usize> {
+    let Some(search) = search else {
+        return vec![];
+    };
+    let search = search.to_lowercase();
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| text(line).to_lowercase().contains(&search))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+fn text(line: &Line) -> String {
+    line.spans.iter().map(|s| s.content.as_ref()).collect()
+}
+
+/// Pretty prints the document the way `serde_json::to_string_pretty` does,
+/// with keys, strings, numbers and literals in their own colours.
+pub fn lines(document: &Value) -> Vec<Line<'static>> {
+    let mut lines = vec![];
+    push(&mut lines, 0, None, document, false);
+    lines
+}
+
+fn push(
+    lines: &mut Vec<Line<'static>>,
+    indent: usize,
+    key: Option<&str>,
+    value: &Value,
+    comma: bool,
+) {
+    let mut spans = vec![Span::raw("  ".repeat(indent))];
+    if let Some(key) = key {
+        spans.push(Span::styled(quote(key), Style::default().fg(Color::Cyan)));
+        spans.push(Span::raw(": "));
+    }
+    let end = if comma { "," } else { "" };
+
+    let children: Vec<(Option<String>, &Value)> = match value {
+        Value::Object(map) if !map.is_empty() => {
+            map.iter().map(|(k, v)| (Some(k.clone()), v)).collect()
+        }
+        Value::Array(array) if !array.is_empty() => array.iter().map(|v| (None, v)).collect(),
+        scalar => {
+            spans.push(scalar_span(scalar));
+            spans.push(Span::raw(end));
+            lines.push(Line::from(spans));
+            return;
+        }
+    };
+
+    let (open, close) = match value {
+        Value::Object(_) => ("{", "}"),
+        _ => ("[", "]"),
+    };
+    spans.push(Span::raw(open));
+    lines.push(Line::from(spans));
+
+    let last = children.len() - 1;
+    for (i, (key, child)) in children.into_iter().enumerate() {
+        push(lines, indent + 1, key.as_deref(), child, i != last);
+    }
+
+    lines.push(Line::from(format!(
+        "{}{}{}",
+        "  ".repeat(indent),
+        close,
+        end
+    )));
+}
+
+fn scalar_span(value: &Value) -> Span<'static> {
+    let color = match value {
+        Value::String(_) => Color::Green,
+        Value::Number(_) => Color::Yellow,
+        Value::Bool(_) => Color::Magenta,
+        Value::Null => Color::Red,
+        Value::Object(_) | Value::Array(_) => Color::Reset,
+    };
+    Span::styled(value.to_string(), Style::default().fg(color))
+}
+
+fn quote(key: &str) -> String {
+    Value::String(key.to_string()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::raw::{lines, text, RawPane, RawPosition};
+
+    #[test]
+    fn pretty_print() {
+        let doc =
+            json!({"status": {"state": "running", "load": [1, 2.5], "clean": false, "empty": {}}});
+
+        let printed: Vec<String> = lines(&doc).iter().map(text).collect();
+
+        assert_eq!(
+            printed.join("\n"),
+            serde_json::to_string_pretty(&doc).unwrap()
+        );
+    }
+
+    #[test]
+    fn toggling_keeps_search() {
+        let doc = json!({"status": {"state": "running", "load": 1}});
+        let mut pane = RawPane::default();
+
+        pane.toggle();
+        pane.set_search(&doc, "load");
+        pane.toggle();
+        assert_eq!(pane.position, Some(RawPosition::Bottom));
+        pane.toggle();
+        assert!(!pane.is_shown());
+        pane.toggle();
+        assert_eq!(pane.search(), Some("load"));
+        assert_eq!(pane.scroll, 2);
+    }
+
+    #[test]
+    fn current_match_is_kept_within_matches() {
+        let doc = json!({"load": [{"load": 1}, {"load": 2}]});
+        let mut pane = RawPane::default();
+
+        pane.set_search(&doc, "load");
+        pane.jump(&doc, 2);
+        assert_eq!(pane.current, 2);
+
+        let doc = json!({"load": 1});
+        pane.refresh(&doc);
+        assert_eq!(pane.current, 0);
+        pane.jump(&doc, 1);
+        assert_eq!(pane.scroll, 1);
+    }
+}
@@ -0,0 +1,150 @@
+use std::{fmt, path::PathBuf, time::Duration};
+
+use eyre::eyre;
+use eyre::Result;
+use serde_json::Value;
+use tokio::sync::mpsc::Receiver;
+
+use crate::{decode::InputFormat, exec, file, httpclient, sse, tail, ws};
+
+/// Longest a poll may take before it counts as failed.
+pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Documents a stream can push before it waits for them to be read, so a
+/// busy stream in a hidden view can't grow without limit.
+pub const STREAM_BUFFER: usize = 64;
+
+/// A polled document together with what is known about the response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fetched {
+    pub value: Value,
+    /// Size of the payload in bytes, after decompression.
+    pub size: usize,
+    /// Status code of http responses.
+    pub status: Option<u16>,
+}
+
+/// A response that arrived but could not be used, it still tells the
+/// status and size of what was received.
+#[derive(Debug)]
+pub struct ResponseError {
+    pub status: u16,
+    pub size: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ResponseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Could not fetch data: {}", self.message)
+    }
+}
+
+impl std::error::Error for ResponseError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Http(String),
+    Exec(String),
+    Sse(String),
+    WebSocket {
+        url: String,
+        subscribe: Option<String>,
+    },
+    Tail(PathBuf),
+    File(PathBuf),
+}
+
+impl Source {
+    /// Picks the kind of source from the url scheme, in any case. Anything
+    /// without a scheme is read as a file.
+    pub fn url(url: String) -> Result<Self> {
+        let Some((scheme, rest)) = url.split_once("://") else {
+            return Ok(Source::File(PathBuf::from(url)));
+        };
+        match scheme.to_ascii_lowercase().as_str() {
+            "http" | "https" => Ok(Source::Http(url)),
+            "ws" | "wss" => Ok(Source::WebSocket {
+                url,
+                subscribe: None,
+            }),
+            "file" => Ok(Source::File(PathBuf::from(rest))),
+            _ => Err(eyre!(
+                "Unsupported scheme {}:// in {}, use http, https, ws, wss or file",
+                scheme,
+                url
+            )),
+        }
+    }
+
+    /// Streaming sources push documents through `subscribe` instead of
+    /// being polled with `fetch`.
+    pub fn is_stream(&self) -> bool {
+        matches!(
+            self,
+            Source::Sse(_) | Source::WebSocket { .. } | Source::Tail(_)
+        )
+    }
+
+    pub async fn fetch(&self, format: InputFormat) -> Result<Fetched> {
+        match self {
+            Source::Http(url) => httpclient::fetch(url, format).await,
+            Source::Exec(command) => tokio::time::timeout(TIMEOUT, exec::run(command, format))
+                .await
+                .map_err(|_| eyre!("Command did not finish within {}s", TIMEOUT.as_secs()))?,
+            Source::File(path) => file::read(path, format).await,
+            Source::Sse(_) | Source::WebSocket { .. } | Source::Tail(_) => {
+                Err(eyre!("{} can not be polled", self))
+            }
+        }
+    }
+
+    pub fn subscribe(&self) -> Option<Receiver<Result<Value>>> {
+        match self {
+            Source::Sse(url) => Some(sse::subscribe(url)),
+            Source::WebSocket { url, subscribe } => Some(ws::subscribe(url, subscribe.as_deref())),
+            Source::Tail(path) => Some(tail::subscribe(path)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Http(url) => write!(f, "{}", url),
+            Source::Exec(command) => write!(f, "$ {}", command),
+            Source::Sse(url) => write!(f, "sse {}", url),
+            Source::WebSocket { url, .. } => write!(f, "{}", url),
+            Source::Tail(path) => write!(f, "tail {}", path.display()),
+            Source::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::source::Source;
+
+    #[test]
+    fn schemes() {
+        assert_eq!(
+            Source::url("HTTP://a/b.json".to_string()).unwrap(),
+            Source::Http("HTTP://a/b.json".to_string())
+        );
+        assert!(matches!(
+            Source::url("Wss://a".to_string()).unwrap(),
+            Source::WebSocket { .. }
+        ));
+        assert_eq!(
+            Source::url("file:///tmp/a.json".to_string()).unwrap(),
+            Source::File(PathBuf::from("/tmp/a.json"))
+        );
+        assert_eq!(
+            Source::url("status.json".to_string()).unwrap(),
+            Source::File(PathBuf::from("status.json"))
+        );
+        assert!(Source::url("ftp://a/b.json".to_string()).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+use std::time::Duration;
+
+use eyre::eyre;
+use eyre::Result;
+use reqwest::header::{ACCEPT, CACHE_CONTROL};
+use serde_json::Value;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+
+use crate::source::STREAM_BUFFER;
+
+const DEFAULT_RETRY: Duration = Duration::from_secs(3);
+
+/// Connects to a `text/event-stream` endpoint and sends the json in every
+/// `data:` event. Reconnects when the stream ends, resuming from the last
+/// seen event id. The task stops when the receiver is dropped.
+pub fn subscribe(url: &str) -> Receiver<Result<Value>> {
+    let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+    let url = url.to_string();
+    tokio::spawn(async move {
+        let client = reqwest::Client::new();
+        let mut parser = EventParser::default();
+        loop {
+            if let Err(e) = listen(&client, &url, &mut parser, &tx).await {
+                if tx.send(Err(e)).await.is_err() {
+                    return;
+                }
+            }
+            if tx.is_closed() {
+                return;
+            }
+            tokio::time::sleep(parser.retry.unwrap_or(DEFAULT_RETRY)).await;
+        }
+    });
+    rx
+}
+
+async fn listen(
+    client: &reqwest::Client,
+    url: &str,
+    parser: &mut EventParser,
+    tx: &Sender<Result<Value>>,
+) -> Result<()> {
+    let mut request = client
+        .get(url)
+        .header(ACCEPT, "text/event-stream")
+        .header(CACHE_CONTROL, "no-cache");
+    if let Some(id) = &parser.last_event_id {
+        request = request.header("Last-Event-ID", id);
+    }
+
+    let mut response = request.send().await?.error_for_status()?;
+    parser.reset();
+
+    while let Some(chunk) = response.chunk().await? {
+        for event in parser.feed(&chunk) {
+            let value = serde_json::from_str(&event.data)
+                .map_err(|e| eyre!("Could not parse event: {}", e));
+            if tx.send(value).await.is_err() {
+                return Ok(());
+            }
+        }
+    }
+    Err(eyre!("Event stream closed, reconnecting"))
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SseEvent {
+    pub id: Option<String>,
+    pub event: Option<String>,
+    pub data: String,
+}
+
+/// Incremental parser for the `text/event-stream` format.
+#[derive(Debug, Default)]
+pub struct EventParser {
+    buffer: Vec<u8>,
+    data: String,
+    event: Option<String>,
+    pub last_event_id: Option<String>,
+    pub retry: Option<Duration>,
+}
+
+impl EventParser {
+    /// Drops any partially received event, used when reconnecting.
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.data.clear();
+        self.event = None;
+    }
+
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = vec![];
+
+        while let Some(end) = self.buffer.iter().position(|b| *b == b'\n' || *b == b'\r') {
+            let line = String::from_utf8_lossy(&self.buffer[..end]).to_string();
+            let mut consumed = end + 1;
+            if self.buffer[end] == b'\r' {
+                match self.buffer.get(end + 1) {
+                    Some(b'\n') => consumed += 1,
+                    // Wait for the next chunk to tell if this is a \r\n pair
+                    None => break,
+                    _ => {}
+                }
+            }
+            self.buffer.drain(..consumed);
+
+            if let Some(event) = self.line(&line) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    fn line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+
+        match field {
+            "data" => {
+                self.data.push_str(value);
+                self.data.push('\n');
+            }
+            "event" => self.event = Some(value.to_string()),
+            "id" if !value.contains('\0') => self.last_event_id = Some(value.to_string()),
+            "retry" => {
+                if let Ok(ms) = value.parse::<u64>() {
+                    self.retry = Some(Duration::from_millis(ms));
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = self.event.take();
+        if self.data.is_empty() {
+            return None;
+        }
+
+        let mut data = std::mem::take(&mut self.data);
+        data.pop();
+        Some(SseEvent {
+            id: self.last_event_id.clone(),
+            event,
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::sse::{EventParser, SseEvent};
+
+    #[test]
+    fn parse_events() {
+        let mut parser = EventParser::default();
+        let events = parser.feed(b": comment\nid: 7\nevent: status\ndata: {\"a\":\ndata: 1}\n\n");
+
+        assert_eq!(
+            events,
+            vec![SseEvent {
+                id: Some("7".to_string()),
+                event: Some("status".to_string()),
+                data: "{\"a\":\n1}".to_string(),
+            }]
+        );
+        assert_eq!(parser.last_event_id, Some("7".to_string()));
+    }
+
+    #[test]
+    fn parse_split_chunks() {
+        let mut parser = EventParser::default();
+
+        assert_eq!(parser.feed(b"retry: 500\r\nda"), vec![]);
+        assert_eq!(parser.feed(b"ta: 1\r"), vec![]);
+        let events = parser.feed(b"\n\r\n");
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "1");
+        assert_eq!(parser.retry, Some(Duration::from_millis(500)));
+    }
+}
@@ -0,0 +1,662 @@
+use std::cmp::Ordering;
+
+use ratatui::{text::Span, widgets::TableState};
+use regex::{Regex, RegexBuilder};
+
+use crate::{
+    feed::Feed,
+    format::Formatter,
+    json::{JsonEntity, JsonValue},
+};
+
+/// The cells of a table, kept as entities so the selected one can be
+/// inspected.
+#[derive(Debug, Default)]
+pub struct Grid {
+    pub header: Vec<String>,
+    /// What every column stands for, the pointer of a field and the title
+    /// of any other column, as titles of different fields can be the same.
+    pub keys: Vec<String>,
+    pub rows: Vec<Vec<Option<Entry>>>,
+    pub sort: Option<Sort>,
+    /// Columns hold the fields and rows the results.
+    pub transposed: bool,
+    /// Formatters of every feed, indexed like `Entry::feed`.
+    pub formats: Vec<Vec<(String, Formatter)>>,
+}
+
+impl Grid {
+    /// One row for the latest result of the feed and one for every result
+    /// kept in its history. Transposed there is a row per field and a column
+    /// per result.
+    pub fn stacked(feed: &Feed, transposed: bool) -> Self {
+        let header: Vec<String> = feed
+            .columns
+            .iter()
+            .map(|entity| entity.title.clone())
+            .collect();
+        let keys: Vec<String> = feed
+            .columns
+            .iter()
+            .map(|entity| entity.pointer.clone())
+            .collect();
+
+        let rows = std::iter::once(&feed.columns)
+            .chain(feed.history.iter())
+            .enumerate()
+            .map(|(sample, columns)| row(&keys, columns, 0, sample))
+            .collect();
+
+        let grid = Self {
+            header,
+            keys,
+            rows,
+            sort: None,
+            transposed: false,
+            formats: vec![feed.formats.clone()],
+        };
+        match transposed {
+            true => {
+                let names = (0..=feed.history.len())
+                    .map(|i| match i {
+                        0 => "value".to_string(),
+                        i => format!("-{}", i),
+                    })
+                    .collect();
+                grid.transpose(names)
+            }
+            false => grid,
+        }
+    }
+
+    /// One row per feed, the first column names the feed. Transposed there
+    /// is a row per field and a column per feed. Cells keep the index of
+    /// their feed in `feeds`.
+    pub fn merged(feeds: &[Feed], transposed: bool) -> Self {
+        let formats: Vec<Vec<(String, Formatter)>> =
+            feeds.iter().map(|feed| feed.formats.clone()).collect();
+        let mut header: Vec<String> = vec![];
+        let mut keys: Vec<String> = vec![];
+        for entity in feeds.iter().flat_map(|feed| feed.columns.iter()) {
+            if !keys.contains(&entity.pointer) {
+                header.push(entity.title.clone());
+                keys.push(entity.pointer.clone());
+            }
+        }
+
+        if transposed {
+            let rows = feeds
+                .iter()
+                .enumerate()
+                .map(|(i, feed)| row(&keys, &feed.columns, i, 0))
+                .collect();
+            let grid = Self {
+                header,
+                keys,
+                rows,
+                sort: None,
+                transposed: false,
+                formats,
+            };
+            return grid.transpose(feeds.iter().map(Feed::title).collect());
+        }
+
+        let rows = feeds
+            .iter()
+            .enumerate()
+            .map(|(i, feed)| {
+                let source = Entry {
+                    entity: JsonEntity {
+                        title: "source".to_string(),
+                        value: JsonValue::Text(feed.title()),
+                        pointer: String::new(),
+                    },
+                    feed: i,
+                    sample: 0,
+                };
+                std::iter::once(Some(source))
+                    .chain(row(&keys, &feed.columns, i, 0))
+                    .collect()
+            })
+            .collect();
+
+        header.insert(0, "source".to_string());
+        keys.insert(0, "source".to_string());
+        Self {
+            header,
+            keys,
+            rows,
+            sort: None,
+            transposed: false,
+            formats,
+        }
+    }
+
+    /// Turns every column into a row starting with the column title,
+    /// `names` become the titles of the columns that the rows turned into.
+    fn transpose(self, names: Vec<String>) -> Self {
+        let rows = self
+            .header
+            .into_iter()
+            .zip(self.keys)
+            .enumerate()
+            .map(|(column, (title, pointer))| {
+                let values = self.rows.iter().map(|row| row[column].clone());
+                let (feed, sample) = values
+                    .clone()
+                    .flatten()
+                    .next()
+                    .map(|entry| (entry.feed, entry.sample))
+                    .unwrap_or_default();
+                let name = Entry {
+                    entity: JsonEntity {
+                        title: "field".to_string(),
+                        value: JsonValue::Text(title),
+                        pointer,
+                    },
+                    feed,
+                    sample,
+                };
+                std::iter::once(Some(name)).chain(values).collect()
+            })
+            .collect();
+
+        let header: Vec<String> = std::iter::once("field".to_string()).chain(names).collect();
+        Self {
+            keys: header.clone(),
+            header,
+            rows,
+            sort: None,
+            transposed: true,
+            formats: self.formats,
+        }
+    }
+
+    /// Keeps the rows matching the filter and sorts them.
+    pub fn arrange(mut self, sort: Option<&Sort>, filter: Option<&Filter>) -> Self {
+        if let Some(filter) = filter {
+            let keep: Vec<bool> = self
+                .rows
+                .iter()
+                .map(|row| filter.matches(&self, row))
+                .collect();
+            let mut keep = keep.into_iter();
+            self.rows.retain(|_| keep.next().unwrap_or(false));
+        }
+        let sorted = sort.and_then(|sort| {
+            let column = self.keys.iter().position(|key| key == &sort.key)?;
+            Some((column, sort))
+        });
+        if let Some((column, sort)) = sorted {
+            self.rows.sort_by(|a, b| {
+                let ordering = compare(a[column].as_ref(), b[column].as_ref());
+                match sort.descending {
+                    true => ordering.reverse(),
+                    false => ordering,
+                }
+            });
+            self.sort = Some(sort.clone());
+        }
+        self
+    }
+
+    /// Room for the sort arrow in the header of the sorted column.
+    fn arrow(&self, column: usize) -> u16 {
+        match &self.sort {
+            Some(sort) if sort.key == self.keys[column] => 2,
+            _ => 0,
+        }
+    }
+
+    /// The value as it is shown, with the formatter its feed gives for its
+    /// pointer.
+    pub fn text(&self, entry: &Entry) -> String {
+        let entity = &entry.entity;
+        let formats = self.formats.get(entry.feed).map_or(&[][..], Vec::as_slice);
+        match Formatter::find(formats, &entity.pointer) {
+            Some(formatter) => formatter.apply(&entity.value),
+            None => entity.value.to_string(),
+        }
+    }
+
+    pub fn cell(&self, row: usize, column: usize) -> Option<&Entry> {
+        self.rows.get(row)?.get(column)?.as_ref()
+    }
+
+    /// Width of every column, wide enough for its header and values but
+    /// never wider than `MAX_WIDTH`.
+    pub fn widths(&self) -> Vec<u16> {
+        self.header
+            .iter()
+            .enumerate()
+            .map(|(column, title)| {
+                self.rows
+                    .iter()
+                    .filter_map(|row| row.get(column)?.as_ref())
+                    .map(|entry| width(&self.text(entry)))
+                    .chain(std::iter::once(width(title) + self.arrow(column)))
+                    .max()
+                    .unwrap_or(0)
+                    .min(MAX_WIDTH)
+            })
+            .collect()
+    }
+}
+
+/// Longer values are cut, the full value is shown in the detail popup.
+pub const MAX_WIDTH: u16 = 40;
+/// Space between two columns.
+pub const SPACING: u16 = 1;
+
+fn width(text: &str) -> u16 {
+    Span::raw(text).width().min(u16::MAX as usize) as u16
+}
+
+/// Missing cells are sorted last.
+fn compare(a: Option<&Entry>, b: Option<&Entry>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.entity.value.compare(&b.entity.value),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// A cell of the grid and the feed it came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub entity: JsonEntity,
+    /// Index of the feed among the feeds the grid was built from.
+    pub feed: usize,
+    /// 0 for the latest result of the feed, `n` for the `n`th result
+    /// back in its history.
+    pub sample: usize,
+}
+
+/// Sorted by the column with that key, so every table sorts by its own
+/// column of that field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sort {
+    pub key: String,
+    pub descending: bool,
+}
+
+/// Rows shown in a table, `title=value` keeps rows where that field has
+/// the value and anything else is a case insensitive regex matched
+/// against every cell.
+#[derive(Debug, Clone)]
+pub enum Filter {
+    /// Matched as a pattern in tables without that field.
+    Field {
+        title: String,
+        value: String,
+        pattern: Regex,
+    },
+    Pattern(Regex),
+}
+
+impl Filter {
+    pub fn parse(input: &str) -> Self {
+        let pattern = pattern(input);
+        match input.split_once('=') {
+            Some((title, value)) if !title.trim().is_empty() => Filter::Field {
+                title: title.trim().to_string(),
+                value: value.trim().to_string(),
+                pattern,
+            },
+            _ => Filter::Pattern(pattern),
+        }
+    }
+
+    /// Matches the values as they are shown in the grid. A transposed grid
+    /// keeps the row of the field when any of its results has the value.
+    pub fn matches(&self, grid: &Grid, row: &[Option<Entry>]) -> bool {
+        let (Filter::Field { pattern, .. } | Filter::Pattern(pattern)) = self;
+        let shows = |entry: &Option<Entry>, value: &str| {
+            entry
+                .as_ref()
+                .is_some_and(|entry| grid.text(entry) == value)
+        };
+        match self {
+            Filter::Field { title, value, .. }
+                if grid.transposed && grid.rows.iter().any(|row| is_field(row, title)) =>
+            {
+                is_field(row, title) && row[1..].iter().any(|entry| shows(entry, value))
+            }
+            Filter::Field { title, value, .. }
+                if !grid.transposed
+                    && grid.header.iter().any(|h| h.eq_ignore_ascii_case(title)) =>
+            {
+                grid.header
+                    .iter()
+                    .zip(row)
+                    .any(|(h, entry)| h.eq_ignore_ascii_case(title) && shows(entry, value))
+            }
+            _ => row
+                .iter()
+                .flatten()
+                .any(|entry| pattern.is_match(&grid.text(entry))),
+        }
+    }
+}
+
+/// Whether the row of a transposed grid holds that field.
+fn is_field(row: &[Option<Entry>], title: &str) -> bool {
+    matches!(
+        row.first(),
+        Some(Some(Entry { entity: JsonEntity { value: JsonValue::Text(field), .. }, .. }))
+            if field.eq_ignore_ascii_case(title)
+    )
+}
+
+/// Text that is not a valid regex is matched as it is.
+fn pattern(input: &str) -> Regex {
+    RegexBuilder::new(input)
+        .case_insensitive(true)
+        .build()
+        .or_else(|_| {
+            RegexBuilder::new(&regex::escape(input))
+                .case_insensitive(true)
+                .build()
+        })
+        .expect("escaped regex")
+}
+
+impl std::fmt::Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Filter::Field { title, value, .. } => write!(f, "{}={}", title, value),
+            Filter::Pattern(pattern) => write!(f, "{}", pattern),
+        }
+    }
+}
+
+fn row(keys: &[String], columns: &[JsonEntity], feed: usize, sample: usize) -> Vec<Option<Entry>> {
+    keys.iter()
+        .map(|pointer| {
+            let entity = columns.iter().find(|entity| &entity.pointer == pointer)?;
+            Some(Entry {
+                entity: entity.clone(),
+                feed,
+                sample,
+            })
+        })
+        .collect()
+}
+
+/// The selected row and column of a table.
+#[derive(Debug, Default)]
+pub struct Cursor {
+    pub state: TableState,
+    pub column: usize,
+    /// First column shown after scrolling horizontally.
+    offset: usize,
+}
+
+impl Cursor {
+    pub fn selected(&self) -> Option<usize> {
+        self.state.selected()
+    }
+
+    /// Moves the selected row, the first move selects the first row.
+    pub fn move_by(&mut self, delta: isize, rows: usize) {
+        let row = match self.state.selected() {
+            Some(row) => row.saturating_add_signed(delta),
+            None => 0,
+        };
+        self.move_to(row, rows);
+    }
+
+    /// Selects the row, or the last one if it is past the end.
+    pub fn move_to(&mut self, row: usize, rows: usize) {
+        match rows {
+            0 => self.state.select(None),
+            _ => self.state.select(Some(row.min(rows - 1))),
+        }
+    }
+
+    pub fn move_column(&mut self, delta: isize, columns: usize) {
+        self.column = self
+            .column
+            .saturating_add_signed(delta)
+            .min(columns.saturating_sub(1));
+    }
+
+    /// Columns that fit in `width`, scrolled so the selected column is
+    /// shown. A frozen first column is shown however far it is scrolled.
+    pub fn visible(&mut self, widths: &[u16], width: u16, frozen: bool) -> Vec<usize> {
+        let frozen = frozen && !widths.is_empty();
+        let first = usize::from(frozen);
+        let available = match frozen {
+            true => width.saturating_sub(widths[0] + SPACING),
+            false => width,
+        };
+
+        self.offset = self.offset.max(first);
+        if self.column >= first {
+            self.offset = self.offset.min(self.column);
+        }
+        let mut shown = fit(widths, self.offset, available);
+        while self.column >= self.offset + shown.len() && self.offset < self.column {
+            self.offset += 1;
+            shown = fit(widths, self.offset, available);
+        }
+
+        match frozen {
+            true => std::iter::once(0).chain(shown).collect(),
+            false => shown,
+        }
+    }
+
+    /// Keeps the selection inside the table after it has shrunk.
+    pub fn clamp(&mut self, rows: usize, columns: usize) {
+        if let Some(row) = self.state.selected() {
+            self.move_to(row, rows);
+        }
+        self.column = self.column.min(columns.saturating_sub(1));
+    }
+}
+
+/// Columns from `offset` that fit in `width`, at least one.
+fn fit(widths: &[u16], offset: usize, width: u16) -> Vec<usize> {
+    let mut used = 0;
+    (offset..widths.len())
+        .take_while(|&column| {
+            let fits = used == 0 || used + widths[column] <= width;
+            used += widths[column] + SPACING;
+            fits
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        format::Formatter,
+        json::{JsonEntity, JsonValue},
+        table::{row, Cursor, Entry, Filter, Grid, Sort},
+    };
+
+    fn text(title: &str, value: &str) -> Option<Entry> {
+        Some(Entry {
+            entity: JsonEntity {
+                title: title.to_string(),
+                value: JsonValue::Text(value.to_string()),
+                pointer: format!("/{}", title),
+            },
+            feed: 0,
+            sample: 0,
+        })
+    }
+
+    fn workers() -> Grid {
+        Grid {
+            header: vec!["state".to_string(), "load".to_string()],
+            keys: vec!["/state".to_string(), "/load".to_string()],
+            rows: vec![
+                vec![text("state", "RUNNING"), entity("load", 10)],
+                vec![text("state", "FAILED"), None],
+                vec![text("state", "FAILED"), entity("load", 2)],
+            ],
+            sort: None,
+            transposed: false,
+            formats: vec![],
+        }
+    }
+
+    #[test]
+    fn sort_numbers_and_missing_last() {
+        let sort = Sort {
+            key: "/load".to_string(),
+            descending: false,
+        };
+        let grid = workers().arrange(Some(&sort), None);
+
+        assert_eq!(grid.cell(0, 1), entity("load", 2).as_ref());
+        assert_eq!(grid.cell(1, 1), entity("load", 10).as_ref());
+        assert_eq!(grid.cell(2, 1), None);
+
+        let sort = Sort {
+            key: "/missing".to_string(),
+            descending: true,
+        };
+        let grid = workers().arrange(Some(&sort), None);
+        assert_eq!(grid.cell(0, 0), text("state", "RUNNING").as_ref());
+        assert_eq!(grid.sort, None);
+    }
+
+    #[test]
+    fn filter_by_field_or_pattern() {
+        let filter = Filter::parse("STATE=FAILED");
+        assert_eq!(workers().arrange(None, Some(&filter)).rows.len(), 2);
+
+        let filter = Filter::parse("^run");
+        assert_eq!(workers().arrange(None, Some(&filter)).rows.len(), 1);
+
+        let filter = Filter::parse("load=(");
+        assert_eq!(workers().arrange(None, Some(&filter)).rows.len(), 0);
+
+        // Without a state column it is a pattern
+        let filter = Filter::parse("state=FAILED");
+        let mut grid = workers();
+        grid.header[0] = "phase".to_string();
+        assert_eq!(grid.arrange(None, Some(&filter)).rows.len(), 0);
+
+        // Transposed it keeps the row of the field
+        let names = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let grid = workers().transpose(names);
+        let grid = grid.arrange(None, Some(&filter));
+        assert_eq!(grid.rows.len(), 1);
+        assert_eq!(grid.cell(0, 2), text("state", "FAILED").as_ref());
+    }
+
+    fn entity(title: &str, value: i64) -> Option<Entry> {
+        Some(Entry {
+            entity: JsonEntity {
+                title: title.to_string(),
+                value: JsonValue::PosInt(value),
+                pointer: format!("/{}", title),
+            },
+            feed: 0,
+            sample: 0,
+        })
+    }
+
+    #[test]
+    fn columns_are_matched_by_pointer() {
+        let state = |pointer: &str, value: &str| JsonEntity {
+            title: "state".to_string(),
+            value: JsonValue::Text(value.to_string()),
+            pointer: pointer.to_string(),
+        };
+        let columns = [state("/a/state", "ok"), state("/b/state", "failed")];
+        let keys = ["/a/state".to_string(), "/b/state".to_string()];
+
+        let cells = row(&keys, &columns, 0, 0);
+        assert_eq!(cells[0].as_ref().unwrap().entity, columns[0]);
+        assert_eq!(cells[1].as_ref().unwrap().entity, columns[1]);
+    }
+
+    #[test]
+    fn formats_cells_with_their_feed() {
+        let mut other = entity("load", 1024).unwrap();
+        other.feed = 1;
+        let grid = Grid {
+            header: vec!["load".to_string()],
+            keys: vec!["/load".to_string()],
+            rows: vec![vec![entity("load", 1024)], vec![Some(other)]],
+            sort: None,
+            transposed: false,
+            formats: vec![vec![("/load".to_string(), Formatter::Bytes)], vec![]],
+        };
+
+        assert_eq!(grid.text(grid.cell(0, 0).unwrap()), "1.02 kB");
+        assert_eq!(grid.text(grid.cell(1, 0).unwrap()), "1024");
+    }
+
+    #[test]
+    fn transpose_turns_columns_into_rows() {
+        let grid = Grid {
+            header: vec!["a".to_string(), "b".to_string()],
+            keys: vec!["/a".to_string(), "/b".to_string()],
+            rows: vec![
+                vec![entity("a", 1), entity("b", 2)],
+                vec![entity("a", 3), None],
+            ],
+            sort: None,
+            transposed: false,
+            formats: vec![],
+        };
+
+        let grid = grid.transpose(vec!["value".to_string(), "-1".to_string()]);
+
+        assert_eq!(grid.header, vec!["field", "value", "-1"]);
+        assert_eq!(grid.rows.len(), 2);
+        let name = grid.cell(1, 0).unwrap();
+        assert_eq!(name.entity.value, JsonValue::Text("b".to_string()));
+        assert_eq!(name.entity.pointer, "/b");
+        assert_eq!(grid.cell(0, 2), entity("a", 3).as_ref());
+        assert_eq!(grid.cell(1, 2), None);
+    }
+
+    #[test]
+    fn scrolls_to_selected_column() {
+        let mut cursor = Cursor::default();
+        let widths = [10, 10, 10, 10, 10];
+
+        assert_eq!(cursor.visible(&widths, 25, false), vec![0, 1]);
+        cursor.move_column(3, widths.len());
+        assert_eq!(cursor.visible(&widths, 25, false), vec![2, 3]);
+        cursor.move_column(-1, widths.len());
+        assert_eq!(cursor.visible(&widths, 25, false), vec![2, 3]);
+        cursor.move_column(1, widths.len());
+        assert_eq!(cursor.visible(&widths, 25, true), vec![0, 3]);
+        cursor.move_column(-3, widths.len());
+        assert_eq!(cursor.visible(&widths, 25, true), vec![0, 3]);
+        assert_eq!(cursor.visible(&widths, 5, false), vec![0]);
+    }
+
+    #[test]
+    fn cursor_stays_inside_table() {
+        let mut cursor = Cursor::default();
+        assert_eq!(cursor.selected(), None);
+
+        cursor.move_by(1, 3);
+        assert_eq!(cursor.selected(), Some(0));
+        cursor.move_by(10, 3);
+        assert_eq!(cursor.selected(), Some(2));
+        cursor.move_by(-1, 3);
+        assert_eq!(cursor.selected(), Some(1));
+        cursor.move_to(usize::MAX, 3);
+        assert_eq!(cursor.selected(), Some(2));
+
+        cursor.move_column(5, 4);
+        assert_eq!(cursor.column, 3);
+        cursor.clamp(1, 2);
+        assert_eq!(cursor.selected(), Some(0));
+        assert_eq!(cursor.column, 1);
+        cursor.clamp(0, 0);
+        assert_eq!(cursor.selected(), None);
+        assert_eq!(cursor.column, 0);
+    }
+}
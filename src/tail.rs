@@ -0,0 +1,220 @@
+use std::{
+    io::SeekFrom,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use eyre::eyre;
+use eyre::Result;
+use serde_json::Value;
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt},
+    sync::mpsc::{self, Receiver},
+};
+
+use crate::source::STREAM_BUFFER;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How much of an existing file is read on start to find its last record,
+/// so it is shown without waiting for the next write.
+const BACKLOG: u64 = 64 * 1024;
+
+/// Follows a json lines file and sends every appended line. Survives the
+/// file being truncated or replaced, e.g. by log rotation.
+pub fn subscribe(path: &Path) -> Receiver<Result<Value>> {
+    let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+    let mut tail = Tail::new(path);
+    tokio::spawn(async move {
+        let mut last_error = None;
+        loop {
+            match tail.poll().await {
+                Ok(values) => {
+                    last_error = None;
+                    for value in values {
+                        if tx.send(value).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+                // Only report an error once, the file is often missing for a
+                // moment while being rotated
+                Err(e) if last_error.as_ref() != Some(&e.to_string()) => {
+                    last_error = Some(e.to_string());
+                    if tx.send(Err(e)).await.is_err() {
+                        return;
+                    }
+                }
+                Err(_) => {}
+            }
+            if tx.is_closed() {
+                return;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+    rx
+}
+
+pub struct Tail {
+    path: PathBuf,
+    file: Option<File>,
+    id: u64,
+    offset: u64,
+    partial: Vec<u8>,
+    started: bool,
+}
+
+impl Tail {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            file: None,
+            id: 0,
+            offset: 0,
+            partial: vec![],
+            started: false,
+        }
+    }
+
+    /// Reads the lines appended since last call.
+    pub async fn poll(&mut self) -> Result<Vec<Result<Value>>> {
+        let metadata = tokio::fs::metadata(&self.path)
+            .await
+            .map_err(|e| eyre!("Could not read {}: {}", self.path.display(), e))?;
+
+        let id = file_id(&metadata);
+        if self.file.is_none() || id != self.id {
+            self.file = Some(File::open(&self.path).await?);
+            self.id = id;
+            self.offset = 0;
+            self.partial.clear();
+        }
+        if metadata.len() < self.offset {
+            self.offset = 0;
+            self.partial.clear();
+        }
+
+        let backlog = !self.started;
+        let skip_partial = backlog && metadata.len() > BACKLOG;
+        if backlog {
+            self.offset = metadata.len().saturating_sub(BACKLOG);
+            self.started = true;
+        }
+
+        let file = self.file.as_mut().expect("file is opened above");
+        file.seek(SeekFrom::Start(self.offset)).await?;
+        let mut bytes = vec![];
+        self.offset += file.read_to_end(&mut bytes).await? as u64;
+        self.partial.extend_from_slice(&bytes);
+
+        let mut lines: Vec<Vec<u8>> = vec![];
+        while let Some(end) = self.partial.iter().position(|b| *b == b'\n') {
+            lines.push(self.partial.drain(..=end).collect());
+        }
+        if skip_partial && !lines.is_empty() {
+            lines.remove(0);
+        }
+
+        let mut lines: Vec<String> = lines
+            .iter()
+            .map(|line| String::from_utf8_lossy(line).trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect();
+        // Older records were written before tjson started, replaying them would
+        // run hooks and raise alerts for things that are long over
+        if backlog {
+            lines.drain(..lines.len().saturating_sub(1));
+        }
+
+        Ok(lines
+            .iter()
+            .map(|line| {
+                serde_json::from_str(line).map_err(|e| eyre!("Could not parse line: {}", e))
+            })
+            .collect())
+    }
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &std::fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.ino()
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &std::fs::Metadata) -> u64 {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Write};
+
+    use serde_json::{json, Value};
+
+    use crate::tail::Tail;
+
+    async fn poll(tail: &mut Tail) -> Vec<Value> {
+        tail.poll()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|v| v.unwrap())
+            .collect()
+    }
+
+    fn append(path: &std::path::Path, text: &str) {
+        let mut f = fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .unwrap();
+        f.write_all(text.as_bytes()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn follow_rotation_and_truncation() {
+        let dir = std::env::temp_dir().join(format!("tjson-tail-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("jobs.jsonl");
+        fs::write(&path, "{\"n\": 1}\n").unwrap();
+
+        let mut tail = Tail::new(&path);
+        assert_eq!(poll(&mut tail).await, vec![json!({"n": 1})]);
+
+        append(&path, "{\"n\": 2}\n{\"n\":");
+        assert_eq!(poll(&mut tail).await, vec![json!({"n": 2})]);
+        append(&path, " 3}\n");
+        assert_eq!(poll(&mut tail).await, vec![json!({"n": 3})]);
+
+        fs::rename(&path, dir.join("jobs.jsonl.1")).unwrap();
+        fs::write(&path, "{\"n\": 4}\n").unwrap();
+        assert_eq!(poll(&mut tail).await, vec![json!({"n": 4})]);
+
+        fs::write(&path, "{\"n\":5}\n").unwrap();
+        assert_eq!(poll(&mut tail).await, vec![json!({"n": 5})]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn start_from_the_last_record() {
+        let dir = std::env::temp_dir().join(format!("tjson-backlog-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("jobs.jsonl");
+        fs::write(&path, "{\"n\": 1}\n{\"n\": 2}\n\n").unwrap();
+
+        let mut tail = Tail::new(&path);
+        assert_eq!(poll(&mut tail).await, vec![json!({"n": 2})]);
+
+        append(&path, "{\"n\": 3}\n{\"n\": 4}\n");
+        assert_eq!(
+            poll(&mut tail).await,
+            vec![json!({"n": 3}), json!({"n": 4})]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
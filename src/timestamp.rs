@@ -0,0 +1,49 @@
+use chrono::{DateTime, TimeZone, Utc};
+use serde_json::Value;
+
+/// Epoch values above this are taken to be in milliseconds, in seconds it
+/// would be more than a thousand years from now.
+const MILLIS_FROM: f64 = 1e11;
+
+/// Reads an RFC 3339 string or a number of seconds or milliseconds since
+/// the epoch, numbers may also be given as strings.
+pub fn parse(value: &Value) -> Option<DateTime<Utc>> {
+    match value {
+        Value::Number(n) => from_epoch(n.as_f64()?),
+        Value::String(s) => match DateTime::parse_from_rfc3339(s.trim()) {
+            Ok(time) => Some(time.with_timezone(&Utc)),
+            Err(_) => from_epoch(s.trim().parse().ok()?),
+        },
+        _ => None,
+    }
+}
+
+fn from_epoch(n: f64) -> Option<DateTime<Utc>> {
+    let millis = match n.abs() >= MILLIS_FROM {
+        true => n,
+        false => n * 1000.0,
+    };
+    Utc.timestamp_millis_opt(millis as i64).single()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+
+    use crate::timestamp::parse;
+
+    #[test]
+    fn formats() {
+        let expected = Utc.with_ymd_and_hms(2024, 5, 1, 12, 30, 0).single();
+
+        assert_eq!(parse(&json!("2024-05-01T12:30:00Z")), expected);
+        assert_eq!(parse(&json!("2024-05-01T14:30:00+02:00")), expected);
+        assert_eq!(parse(&json!(1714566600)), expected);
+        assert_eq!(parse(&json!(1714566600000u64)), expected);
+        assert_eq!(parse(&json!("1714566600")), expected);
+        assert_eq!(parse(&json!(1714566600.0)), expected);
+        assert_eq!(parse(&json!("yesterday")), None);
+        assert_eq!(parse(&json!(true)), None);
+    }
+}
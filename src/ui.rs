@@ -1,170 +1,1186 @@
-pub mod ui {
-    use eyre::eyre;
-    use std::time::{Duration, Instant};
-
-    use color_eyre::Result;
-    use crossterm::event::KeyCode;
-    use ratatui::{
-        prelude::{Constraint, Direction, Layout},
-        style::{Color, Style},
-        widgets::{Block, Borders, Cell, Row, Table, TableState},
-        Frame,
-    };
+use eyre::eyre;
+use std::{io::Write, time::Instant};
 
-    use crate::{
-        args::args::TJsonArgs,
-        httpclient,
-        json::json::{get_cell, EntityResult, JsonEntity},
-        tui::{Event, Tui},
-    };
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::Rect,
+    prelude::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, Tabs, Wrap},
+    Frame,
+};
+use serde_json::Value;
+
+use crate::{
+    args::{AlertStyle, SourceLayout, ViewArgs},
+    diff::DiffView,
+    explorer::Explorer,
+    feed::Feed,
+    format::{self, Formatter},
+    keymap::{Action, Keymap},
+    prompt::{Prompt, PromptEvent},
+    raw::{RawPane, RawPosition},
+    table::{Cursor, Entry, Filter, Grid, Sort},
+    tui::{Event, Tui},
+    view::View,
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AppState {
+    #[default]
+    Running,
+    Quitting,
+}
+
+/// What the main area shows.
+#[derive(Debug, Default)]
+pub enum Mode {
+    #[default]
+    Table,
+    Explorer(Explorer),
+    Diff(DiffView),
+}
+
+/// What the text typed into the prompt is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptKind {
+    Search,
+    Filter,
+}
+
+pub struct App {
+    views: Vec<View>,
+    view: usize,
+    poll_hidden: bool,
+    layout: SourceLayout,
+    /// Keep the first column in place when scrolling horizontally.
+    freeze: bool,
+    /// Show a row per field instead of a column per field.
+    transposed: bool,
+    sort: Option<Sort>,
+    filter: Option<Filter>,
+    mode: Mode,
+    raw: RawPane,
+    prompt: Option<(PromptKind, Prompt)>,
+    /// Whether the selected cell is shown in a popup.
+    detail: bool,
+    keymap: Keymap,
+    /// Whether the keys are listed in a popup.
+    help: bool,
+    /// Polling is stopped and the table left as it is.
+    paused: bool,
+    alert: AlertStyle,
+    /// When the latest alert that rang the bell started.
+    rung: Option<Instant>,
+    /// Shown in the status bar until the next key.
+    notice: Option<String>,
+    state: AppState,
+}
+
+impl App {
+    pub fn new(
+        views: Vec<ViewArgs>,
+        layout: SourceLayout,
+        poll_hidden: bool,
+        freeze: bool,
+        alert: AlertStyle,
+        keymap: Keymap,
+    ) -> Self {
+        let views: Vec<View> = views.into_iter().map(View::new).collect();
+        // Without pointers or calcs there is nothing to show but the document
+        let empty = |feed: &Feed| feed.pointers.is_empty() && feed.calcs.is_empty();
+        let mode = match views[0].feeds.iter().all(empty) {
+            true => Mode::Explorer(Explorer::new()),
+            false => Mode::Table,
+        };
+        Self {
+            views,
+            view: 0,
+            poll_hidden,
+            layout,
+            freeze,
+            transposed: false,
+            sort: None,
+            filter: None,
+            mode,
+            raw: RawPane::default(),
+            prompt: None,
+            detail: false,
+            keymap,
+            help: false,
+            paused: false,
+            alert,
+            rung: None,
+            notice: None,
+            state: AppState::default(),
+        }
+    }
+
+    /// Feeds that should be kept up to date, hidden views are only
+    /// polled when asked for.
+    fn polled_feeds(&mut self) -> impl Iterator<Item = &mut Feed> {
+        let active = self.view;
+        let poll_hidden = self.poll_hidden;
+        self.views
+            .iter_mut()
+            .enumerate()
+            .filter(move |(i, _)| poll_hidden || *i == active)
+            .flat_map(|(_, view)| view.feeds.iter_mut())
+    }
+
+    pub async fn run(&mut self) -> Result<()> {
+        let mut tui = Tui::new()?;
+        tui.enter()?;
+
+        while self.state != AppState::Quitting {
+            tui.draw(|f| self.ui(f).expect("Error drawing"))?;
+            let e = tui.next().await.ok_or(eyre!("Unable to get event"))?; // blocks until next event
+            let message = self.handle_event(e)?;
+            self.update(message)?;
+        }
+        tui.exit()?;
+
+        Ok(())
+    }
+
+    fn handle_event(&mut self, event: Event) -> Result<Message> {
+        let Event::Key(key) = event else {
+            return Ok(Message::Tick);
+        };
+        self.notice = None;
+
+        if self.prompt.is_some() {
+            return Ok(Message::PromptKey(key));
+        }
+
+        // Any key closes the help, also keys that are not bound
+        if self.help {
+            return Ok(match self.keymap.action(&key) {
+                Some(Action::Quit) => Message::Quit,
+                _ => Message::CloseHelp,
+            });
+        }
+
+        let Some(action) = self.keymap.action(&key) else {
+            // Views are picked by their number wherever the table is shown
+            let views = self.views.len();
+            return Ok(match key.code {
+                KeyCode::Char(c @ '1'..='9')
+                    if matches!(self.mode, Mode::Table) && (c as usize - '1' as usize) < views =>
+                {
+                    Message::SelectView(c as usize - '1' as usize)
+                }
+                _ => Message::Tick,
+            });
+        };
+
+        match action {
+            Action::Quit => return Ok(Message::Quit),
+            Action::Help => return Ok(Message::OpenHelp),
+            Action::Refresh => return Ok(Message::Refresh),
+            Action::Pause => return Ok(Message::Pause),
+            Action::Acknowledge => return Ok(Message::Acknowledge),
+            _ => {}
+        }
+
+        if self.detail {
+            let msg = match action {
+                Action::Back | Action::Select => Message::CloseDetail,
+                _ => Message::Tick,
+            };
+            return Ok(msg);
+        }
+
+        if let Mode::Diff(_) = self.mode {
+            let msg = match action {
+                Action::Back | Action::Diff => Message::CloseDiff,
+                Action::Up => Message::DiffUp,
+                Action::Down => Message::DiffDown,
+                Action::TogglePointer => Message::TogglePointer,
+                _ => Message::Tick,
+            };
+            return Ok(msg);
+        }
+
+        if let Mode::Explorer(_) = self.mode {
+            let msg = match action {
+                Action::Back | Action::Explorer => Message::CloseExplorer,
+                Action::Up => Message::ExplorerUp,
+                Action::Down => Message::ExplorerDown,
+                Action::Right => Message::ExplorerExpand,
+                Action::Left => Message::ExplorerCollapse,
+                Action::Select => Message::ExplorerToggle,
+                Action::TogglePointer => Message::TogglePointer,
+                _ => Message::Tick,
+            };
+            return Ok(msg);
+        }
+
+        if self.raw.is_shown() {
+            let msg = match action {
+                Action::Up => Some(Message::ScrollRaw(-1)),
+                Action::Down => Some(Message::ScrollRaw(1)),
+                Action::PageUp => Some(Message::ScrollRaw(-20)),
+                Action::PageDown => Some(Message::ScrollRaw(20)),
+                Action::Top => Some(Message::ScrollRawTo(0)),
+                Action::Bottom => Some(Message::ScrollRawTo(usize::MAX)),
+                Action::Filter => Some(Message::OpenPrompt(PromptKind::Search)),
+                Action::NextMatch => Some(Message::NextMatch(1)),
+                Action::PreviousMatch => Some(Message::NextMatch(-1)),
+                _ => None,
+            };
+            if let Some(msg) = msg {
+                return Ok(msg);
+            }
+        }
+
+        let views = self.views.len();
+        let feeds = self.views[self.view].feeds.len();
+        let focus = self.views[self.view].focus;
+        let msg = match action {
+            Action::Raw => Message::ToggleRaw,
+            Action::Explorer => Message::OpenExplorer,
+            Action::Diff => Message::OpenDiff,
+            Action::NextSource => Message::Focus((focus + 1) % feeds),
+            Action::PreviousSource => Message::Focus((focus + feeds - 1) % feeds),
+            Action::Up => Message::MoveRow(-1),
+            Action::Down => Message::MoveRow(1),
+            Action::PageUp => Message::MoveRow(-PAGE),
+            Action::PageDown => Message::MoveRow(PAGE),
+            Action::Top => Message::MoveRowTo(0),
+            Action::Bottom => Message::MoveRowTo(usize::MAX),
+            Action::Left => Message::MoveColumn(-1),
+            Action::Right => Message::MoveColumn(1),
+            Action::Select => Message::OpenDetail,
+            Action::Transpose => Message::Transpose,
+            Action::Sort => Message::Sort,
+            Action::ClearSort => Message::ClearSort,
+            Action::Filter => Message::OpenPrompt(PromptKind::Filter),
+            Action::NextView => Message::SelectView((self.view + 1) % views),
+            Action::PreviousView => Message::SelectView((self.view + views - 1) % views),
+            _ => Message::Tick,
+        };
+
+        Ok(msg)
+    }
 
-    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
-    pub enum AppState {
-        #[default]
-        Running,
-        Quitting,
-    }
-
-    pub struct App {
-        table_state: TableState,
-        update_source_c: usize,
-        last_updated: Instant,
-        columns: Vec<JsonEntity>,
-        state: AppState,
-        args: TJsonArgs,
-    }
-
-    impl App {
-        pub fn new(args: TJsonArgs) -> Self {
-            Self {
-                args,
-                table_state: TableState::default(),
-                columns: vec![],
-                state: AppState::default(),
-                last_updated: Instant::now() - Duration::new(3, 0),
-                update_source_c: 0,
+    fn update(&mut self, message: Message) -> Result<()> {
+        match message {
+            Message::Quit => self.stop(),
+            Message::Tick => {}
+            Message::SelectView(view) => self.view = view,
+            Message::Focus(feed) => self.views[self.view].focus = feed,
+            Message::OpenExplorer => self.mode = Mode::Explorer(Explorer::new()),
+            Message::CloseExplorer | Message::CloseDiff => self.mode = Mode::Table,
+            Message::OpenDiff => self.mode = Mode::Diff(DiffView::new()),
+            Message::DiffUp | Message::DiffDown => self.browse_diff(message),
+            Message::ExplorerUp
+            | Message::ExplorerDown
+            | Message::ExplorerExpand
+            | Message::ExplorerCollapse
+            | Message::ExplorerToggle => self.explore(message),
+            Message::TogglePointer => self.toggle_pointer(),
+            Message::MoveRow(_) | Message::MoveRowTo(_) | Message::MoveColumn(_) => {
+                self.move_cursor(message)
             }
+            Message::OpenDetail => self.detail = self.selected_cell().is_some(),
+            Message::CloseDetail => self.detail = false,
+            Message::Transpose => self.transposed = !self.transposed,
+            Message::OpenHelp => self.help = true,
+            Message::CloseHelp => self.help = false,
+            Message::Refresh => self.polled_feeds().for_each(|feed| feed.expire()),
+            Message::Pause => self.paused = !self.paused,
+            Message::Acknowledge => self
+                .views
+                .iter_mut()
+                .flat_map(|view| view.feeds.iter_mut())
+                .for_each(|feed| feed.acknowledge()),
+            Message::Sort => self.sort(),
+            Message::ClearSort => self.sort = None,
+            Message::ToggleRaw => self.raw.toggle(),
+            Message::ScrollRaw(delta) => self.raw.scroll_by(delta),
+            Message::ScrollRawTo(line) => self.raw.scroll_to(line),
+            Message::NextMatch(delta) => {
+                let feed = self.views[self.view].focused();
+                if let Some(document) = &feed.document {
+                    self.raw.jump(document, delta);
+                }
+            }
+            Message::OpenPrompt(kind) => {
+                let input = match kind {
+                    PromptKind::Search => self.raw.search().map(str::to_string),
+                    PromptKind::Filter => self.filter.as_ref().map(Filter::to_string),
+                };
+                let label = match kind {
+                    PromptKind::Search => "/",
+                    PromptKind::Filter => "filter: ",
+                };
+                self.prompt = Some((kind, Prompt::new(label, &input.unwrap_or_default())));
+            }
+            Message::PromptKey(key) => self.prompt_key(key),
         }
+        // A refresh fetches once even while paused
+        if !self.paused || message == Message::Refresh {
+            self.poll();
+            self.tick();
+        } else {
+            // and is shown once the fetch it started is done
+            self.views
+                .iter_mut()
+                .flat_map(|view| view.feeds.iter_mut())
+                .filter(|feed| feed.is_fetching())
+                .for_each(Feed::receive);
+        }
+        self.ring()?;
+        Ok(())
+    }
 
-        pub async fn run(&mut self) -> Result<()> {
-            let mut tui = Tui::new()?;
-            tui.enter()?;
+    /// Starts fetching every polled feed that is due.
+    fn poll(&mut self) {
+        let now = Instant::now();
+        self.polled_feeds()
+            .filter(|feed| feed.is_due(now))
+            .for_each(Feed::update);
+    }
 
-            while self.state != AppState::Quitting {
-                tui.draw(|f| self.ui(f).expect("Error drawing"))?;
-                let e = tui.next().await.ok_or(eyre!("Unable to get event"))?; // blocks until next event
-                let message = self.handle_event(e)?;
-                self.update(message).await?;
+    fn prompt_key(&mut self, key: KeyEvent) {
+        let Some((kind, prompt)) = &mut self.prompt else {
+            return;
+        };
+        let kind = *kind;
+        let input = match prompt.handle_key(key) {
+            PromptEvent::Edited => return,
+            PromptEvent::Cancelled => None,
+            PromptEvent::Submitted(input) => Some(input),
+        };
+        self.prompt = None;
+
+        match (kind, input) {
+            (PromptKind::Search, Some(search)) => {
+                let feed = self.views[self.view].focused();
+                if let Some(document) = &feed.document {
+                    self.raw.set_search(document, &search);
+                }
+            }
+            (PromptKind::Filter, Some(filter)) => {
+                self.filter = match filter.trim() {
+                    "" => None,
+                    filter => Some(Filter::parse(filter)),
+                };
             }
-            tui.exit()?;
+            (PromptKind::Search | PromptKind::Filter, None) => {}
+        }
+    }
 
-            Ok(())
+    fn browse_diff(&mut self, message: Message) {
+        let Mode::Diff(diff) = &mut self.mode else {
+            return;
+        };
+        let feed = self.views[self.view].focused();
+        match message {
+            Message::DiffUp => diff.up(),
+            Message::DiffDown => diff.down(feed.changes.len()),
+            _ => {}
         }
+    }
 
-        fn handle_event(&self, event: Event) -> Result<Message> {
-            let now = Instant::now();
-            let elapsed = (now - self.last_updated).as_secs_f64();
-            if elapsed >= 2.0 {
-                return Ok(Message::UpdateSource);
+    /// Adds or removes the pointer selected in the explorer or diff as a
+    /// column of the focused feed.
+    fn toggle_pointer(&mut self) {
+        let feed = self.views[self.view].focused();
+        let pointer = match (&self.mode, &feed.document) {
+            (Mode::Explorer(explorer), Some(document)) => explorer
+                .selected(&explorer.nodes(document))
+                .map(|node| node.pointer.clone()),
+            (Mode::Diff(diff), _) => diff
+                .selected(&feed.changes)
+                .map(|change| change.pointer().to_string()),
+            _ => None,
+        };
+        let Some(pointer) = pointer else {
+            return;
+        };
+        // Arrays have no cell of their own, their items have to be picked
+        let array = feed
+            .document
+            .as_ref()
+            .and_then(|document| document.pointer(&pointer))
+            .is_some_and(Value::is_array);
+        match array {
+            true => self.notice = Some(format!("{} is an array, pick its items", pointer)),
+            false => feed.toggle_pointer(&pointer),
+        }
+    }
+
+    fn explore(&mut self, message: Message) {
+        let Mode::Explorer(explorer) = &mut self.mode else {
+            return;
+        };
+        let feed = self.views[self.view].focused();
+        let Some(document) = &feed.document else {
+            return;
+        };
+        let nodes = explorer.nodes(document);
+        let Some(node) = explorer.selected(&nodes).cloned() else {
+            return;
+        };
+
+        match message {
+            Message::ExplorerUp => explorer.up(),
+            Message::ExplorerDown => explorer.down(nodes.len()),
+            Message::ExplorerExpand => explorer.expand(&node),
+            Message::ExplorerCollapse => explorer.collapse(&nodes),
+            Message::ExplorerToggle => explorer.toggle(&node),
+            _ => {}
+        }
+    }
+
+    /// The grid of the table that keys act on and its cursor.
+    fn table(&mut self) -> (Grid, &mut Cursor) {
+        let view = &mut self.views[self.view];
+        let (grid, cursor) = match self.layout {
+            SourceLayout::Stacked => {
+                let feed = view.focused();
+                (Grid::stacked(feed, self.transposed), &mut feed.cursor)
             }
+            SourceLayout::Merged => (Grid::merged(&view.feeds, self.transposed), &mut view.cursor),
+        };
+        (
+            grid.arrange(self.sort.as_ref(), self.filter.as_ref()),
+            cursor,
+        )
+    }
 
-            let msg = match event {
-                Event::Key(key) => match key.code {
-                    KeyCode::Char('q') => Message::Quit,
-                    _ => Message::Tick,
-                },
-                _ => Message::Tick,
+    /// Sorts by the selected column, ascending first and descending when
+    /// it is already sorted by it.
+    fn sort(&mut self) {
+        let (grid, cursor) = self.table();
+        let Some(key) = grid.keys.get(cursor.column).cloned() else {
+            return;
+        };
+        let descending = self.sort.as_ref()
+            == Some(&Sort {
+                key: key.clone(),
+                descending: false,
+            });
+        self.sort = Some(Sort { key, descending });
+    }
+
+    /// Title of a table, with the filter its rows are matched against.
+    fn title(&self, title: String) -> String {
+        match &self.filter {
+            Some(filter) => format!("{} [filter: {}]", title, filter),
+            None => title,
+        }
+    }
+
+    fn move_cursor(&mut self, message: Message) {
+        let (grid, cursor) = self.table();
+        let rows = grid.rows.len();
+        match message {
+            Message::MoveRow(delta) => cursor.move_by(delta, rows),
+            Message::MoveRowTo(row) => cursor.move_to(row, rows),
+            Message::MoveColumn(delta) => cursor.move_column(delta, grid.header.len()),
+            _ => {}
+        }
+    }
+
+    /// The selected cell together with the feed it came from.
+    fn selected_cell(&mut self) -> Option<(Entry, &Feed)> {
+        let (grid, cursor) = self.table();
+        let row = cursor.selected()?;
+        let entry = grid.cell(row, cursor.column)?.clone();
+        let view = &self.views[self.view];
+        let feed = match self.layout {
+            SourceLayout::Stacked => &view.feeds[view.focus],
+            SourceLayout::Merged => &view.feeds[entry.feed],
+        };
+        Some((entry, feed))
+    }
+
+    /// Reads every stream that is subscribed to, also in hidden views, so
+    /// their documents don't pile up until the view is shown again.
+    fn tick(&mut self) {
+        self.views
+            .iter_mut()
+            .flat_map(|view| view.feeds.iter_mut())
+            .for_each(|feed| feed.receive());
+    }
+
+    /// Rings the terminal bell once for every alert that started since it
+    /// last rang.
+    fn ring(&mut self) -> Result<()> {
+        if self.alert == AlertStyle::Flash {
+            return Ok(());
+        }
+        let latest = self
+            .views
+            .iter()
+            .flat_map(|view| view.feeds.iter())
+            .filter_map(|feed| feed.alert)
+            .max();
+        if latest > self.rung {
+            let mut stderr = std::io::stderr();
+            stderr.write_all(b"\x07")?;
+            stderr.flush()?;
+            self.rung = latest;
+        }
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        self.state = AppState::Quitting
+    }
+
+    fn ui(&mut self, f: &mut Frame) -> Result<()> {
+        let view = &self.views[self.view];
+        let errors: Vec<String> = view
+            .feeds
+            .iter()
+            .flat_map(|feed| {
+                let hook = feed.hook.as_ref().and_then(|hook| hook.error());
+                let hook = hook.map(|e| format!("Hook failed {}", e));
+                feed.error
+                    .clone()
+                    .into_iter()
+                    .chain(hook)
+                    .map(|e| match view.feeds.len() > 1 {
+                        true => format!("{}: {}", feed.title(), e),
+                        false => e,
+                    })
+            })
+            .collect();
+
+        let tabs = self.views.len() > 1;
+        let mut constraints = vec![];
+        if tabs {
+            constraints.push(Constraint::Length(3));
+        }
+        constraints.push(Constraint::Min(0));
+        if !errors.is_empty() {
+            constraints.push(Constraint::Length(errors.len() as u16 + 3));
+        }
+        if self.prompt.is_some() {
+            constraints.push(Constraint::Length(1));
+        }
+        constraints.push(Constraint::Length(1));
+
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(constraints)
+            .split(f.size());
+        let mut chunks = layout.iter().copied();
+
+        if tabs {
+            self.tabs(f, chunks.next().expect("tabs chunk"));
+        }
+
+        let mut body = chunks.next().expect("body chunk");
+        if let Some(position) = self.raw.position {
+            let (direction, split) = match position {
+                RawPosition::Right => (Direction::Horizontal, 50),
+                RawPosition::Bottom => (Direction::Vertical, 60),
             };
+            let panes = Layout::default()
+                .direction(direction)
+                .constraints(vec![
+                    Constraint::Percentage(split),
+                    Constraint::Percentage(100 - split),
+                ])
+                .split(body);
+            body = panes[0];
+            let feed = self.views[self.view].focused();
+            self.raw.render(f, panes[1], feed.document.as_ref());
+        }
 
-            Ok(msg)
-        }
-
-        async fn update(&mut self, message: Message) -> Result<()> {
-            match message {
-                Message::Quit => self.stop(),
-                Message::Tick => self.tick(),
-                Message::UpdateSource => {
-                    let json = httpclient::fetch(&self.args.source).await?;
-
-                    let result: Vec<JsonEntity> = self
-                        .args
-                        .pointers
-                        .iter()
-                        .filter_map(|pointer| get_cell(&json, pointer))
-                        .flat_map(|v| -> Vec<JsonEntity> {
-                            match v {
-                                EntityResult::Entities(cs) => cs,
-                                EntityResult::Entity(c) => vec![c],
-                            }
-                        })
-                        .collect();
-
-                    self.columns = result;
-                    self.last_updated = Instant::now();
-                    self.update_source_c += 1;
+        let toggle = self.key(Action::TogglePointer);
+        let close = self.key(Action::Back);
+        let expand = self.key(Action::Select);
+        match (&mut self.mode, self.layout) {
+            (Mode::Explorer(explorer), _) => {
+                let feed = self.views[self.view].focused();
+                let title = format!(
+                    "Explore: {} ({}: toggle column, {}: expand, {}: close)",
+                    feed.title(),
+                    toggle,
+                    expand,
+                    close
+                );
+                match &feed.document {
+                    Some(document) => explorer.render(f, body, title, document, &feed.pointers),
+                    None => f.render_widget(
+                        Paragraph::new("Waiting for data")
+                            .block(Block::default().borders(Borders::ALL).title(title)),
+                        body,
+                    ),
                 }
             }
-            Ok(())
+            (Mode::Diff(diff), _) => {
+                let feed = self.views[self.view].focused();
+                let title = format!(
+                    "Changes: {} ({}, {}: toggle column, {}: close)",
+                    feed.title(),
+                    feed.changes.len(),
+                    toggle,
+                    close
+                );
+                diff.render(f, body, title, &feed.changes, &feed.pointers);
+            }
+            (Mode::Table, SourceLayout::Stacked) => self.stacked(f, body),
+            (Mode::Table, SourceLayout::Merged) => self.merged(f, body),
         }
 
-        fn tick(&mut self) {
-            // Do nothing
+        if !errors.is_empty() {
+            let p = Paragraph::new(errors.join("\n"))
+                .style(Style::default().fg(Color::Red))
+                .wrap(Wrap { trim: true })
+                .block(Block::default().borders(Borders::ALL).title("Error"));
+            f.render_widget(p, chunks.next().expect("error chunk"));
         }
 
-        fn stop(&mut self) {
-            self.state = AppState::Quitting
+        if self.detail {
+            self.detail(f);
         }
 
-        fn ui(&mut self, f: &mut Frame) -> Result<()> {
-            let layout = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints(vec![Constraint::Percentage(100)])
-                //                    .constraints(vec![Constraint::Percentage(30), Constraint::Percentage(70)])
-                .split(f.size());
-
-            let header_cells: Vec<Cell> = self
-                .columns
-                .iter()
-                .map(|json_entity| json_entity.title.to_uppercase())
-                .map(|h| Cell::from(h).style(Style::default().fg(Color::Green)))
-                .collect();
+        if self.help {
+            self.help(f);
+        }
 
-            let header = Row::new(header_cells);
+        if let Some((_, prompt)) = &self.prompt {
+            f.render_widget(
+                Paragraph::new(prompt.line()),
+                chunks.next().expect("prompt chunk"),
+            );
+        }
 
-            let row_cells: Vec<Cell> = self
-                .columns
-                .iter()
-                .map(|entity| Cell::from(entity.value.to_string()))
-                .collect();
+        self.status(f, chunks.next().expect("status chunk"));
 
-            let rows = vec![Row::new(row_cells)];
+        Ok(())
+    }
 
-            let widths: Vec<Constraint> = self
-                .columns
-                .iter()
-                .map(|_| Constraint::Percentage(15))
-                .collect();
+    /// Popup listing every action and the keys bound to it.
+    fn help(&self, f: &mut Frame) {
+        let rows: Vec<Row> = Action::ALL
+            .iter()
+            .map(|action| {
+                let keys: Vec<String> = self
+                    .keymap
+                    .keys(*action)
+                    .iter()
+                    .map(|key| key.to_string())
+                    .collect();
+                Row::new(vec![
+                    Cell::from(keys.join(", ")).style(Style::default().fg(Color::Green)),
+                    Cell::from(action.description()),
+                ])
+            })
+            .chain(std::iter::once(Row::new(vec![
+                Cell::from("1-9").style(Style::default().fg(Color::Green)),
+                Cell::from("show the view with that number"),
+            ])))
+            .collect();
 
-            let t = Table::new(rows)
-                .header(header)
+        let area = popup(f.size(), 80, 90);
+        let widths = [
+            Constraint::Length(18),
+            Constraint::Length(area.width.saturating_sub(21)),
+        ];
+        f.render_widget(Clear, area);
+        f.render_widget(
+            Table::new(rows)
                 .block(
                     Block::default()
                         .borders(Borders::ALL)
-                        .title(format!("Status: {}", &self.args.source)),
+                        .title("Keys (any key: close)"),
                 )
-                .highlight_symbol("# ")
-                .widths(&widths);
+                .widths(&widths),
+            area,
+        );
+    }
+
+    /// The first key bound to the action, for hints.
+    fn key(&self, action: Action) -> String {
+        self.keymap
+            .keys(action)
+            .first()
+            .map_or("-".to_string(), |key| key.to_string())
+    }
+
+    /// Hint shown in a table title when not all columns fit.
+    fn scroll(&self) -> String {
+        format!(
+            "{}/{}: scroll",
+            self.key(Action::Left),
+            self.key(Action::Right)
+        )
+    }
+
+    /// Bottom line with whether polling is paused and how the focused
+    /// feed was last fetched.
+    fn status(&self, f: &mut Frame, area: Rect) {
+        let now = Instant::now();
+        let mut spans = vec![];
+        if let Some(notice) = &self.notice {
+            spans.push(Span::styled(
+                format!(" {} ", notice),
+                Style::default().fg(Color::Black).bg(Color::Cyan),
+            ));
+            spans.push(Span::raw(" "));
+        }
+        if self.paused {
+            spans.push(Span::styled(
+                " PAUSED ",
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ));
+            spans.push(Span::raw(" "));
+        }
+
+        let alerts = self
+            .views
+            .iter()
+            .flat_map(|view| view.feeds.iter())
+            .any(|feed| feed.alert.is_some());
+        if alerts {
+            spans.push(Span::styled(
+                format!(" ALERT {}: acknowledge ", self.key(Action::Acknowledge)),
+                Style::default()
+                    .fg(Color::White)
+                    .bg(Color::Red)
+                    .add_modifier(Modifier::BOLD),
+            ));
+            spans.push(Span::raw(" "));
+        }
+
+        let view = &self.views[self.view];
+        let feed = &view.feeds[view.focus];
+        let separator = || Span::styled(" | ", Style::default().fg(Color::DarkGray));
+        if view.feeds.len() > 1 {
+            spans.push(Span::raw(format!("{}: ", feed.title())));
+        }
+        match feed.last_success {
+            Some(at) => spans.push(Span::raw(format!("fetched {}", at.format("%H:%M:%S")))),
+            None => spans.push(Span::raw("not fetched yet")),
+        }
+        if let Some(latency) = feed.latency {
+            spans.push(Span::raw(format!(" in {}", format::duration(latency))));
+        }
+        if let Some(status) = feed.status {
+            let color = match status {
+                200..=299 => Color::Green,
+                300..=399 => Color::Yellow,
+                _ => Color::Red,
+            };
+            spans.push(separator());
+            spans.push(Span::styled(
+                format!("HTTP {}", status),
+                Style::default().fg(color),
+            ));
+        }
+        if let Some(size) = feed.size {
+            spans.push(separator());
+            spans.push(Span::raw(format::bytes(size as u64)));
+        }
+        spans.push(separator());
+        spans.push(Span::raw(format!("poll #{}", feed.update_source_c)));
+
+        let text = match (self.paused, feed.next_poll(now)) {
+            (true, _) => format!("{}: resume", self.key(Action::Pause)),
+            (false, Some(next)) => format!("next refresh in {}s", next.as_secs_f64().ceil()),
+            (false, None) => "streaming".to_string(),
+        };
+        spans.push(separator());
+        spans.push(Span::raw(text));
+        spans.push(separator());
+        spans.push(Span::styled(
+            format!("{}: help", self.key(Action::Help)),
+            Style::default().fg(Color::DarkGray),
+        ));
+
+        f.render_widget(Paragraph::new(Line::from(spans)), area);
+    }
+
+    fn tabs(&self, f: &mut Frame, area: Rect) {
+        let titles: Vec<Line> = self
+            .views
+            .iter()
+            .enumerate()
+            .map(|(i, view)| Line::from(format!("{} {}", i + 1, view.name)))
+            .collect();
+
+        let tabs = Tabs::new(titles)
+            .block(Block::default().borders(Borders::ALL).title("Views"))
+            .select(self.view)
+            .highlight_style(Style::default().fg(Color::Green));
+
+        f.render_widget(tabs, area);
+    }
+
+    fn stacked(&mut self, f: &mut Frame, area: Rect) {
+        let sort = self.sort.clone();
+        let filter = self.filter.clone();
+        let title = self.title("Status".to_string());
+        let style = self.alert;
+        let scroll = self.scroll();
+        let view = &mut self.views[self.view];
+        let n = view.feeds.len() as u32;
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Ratio(1, n); view.feeds.len()])
+            .split(area);
+
+        let focus = view.focus;
+        let freeze = self.freeze;
+        let transposed = self.transposed;
+        let many = view.feeds.len() > 1;
+        for (i, (feed, area)) in view.feeds.iter_mut().zip(layout.iter()).enumerate() {
+            let grid = Grid::stacked(feed, transposed).arrange(sort.as_ref(), filter.as_ref());
+            let title = format!(
+                "{}: {}{}{}",
+                title,
+                feed.title(),
+                age(feed),
+                failing(feed.failing())
+            );
+            let alert = feed.is_stale() || feed.failing() > 0;
+            let border = border(many && i == focus, alert, flash(style, feed.alert));
+            let cursor = &mut feed.cursor;
+            render_table(f, *area, title, &scroll, grid, border, freeze, cursor);
+        }
+    }
+
+    fn merged(&mut self, f: &mut Frame, area: Rect) {
+        let title = self.title("Status".to_string());
+        let scroll = self.scroll();
+        let view = &mut self.views[self.view];
+        let grid = Grid::merged(&view.feeds, self.transposed)
+            .arrange(self.sort.as_ref(), self.filter.as_ref());
+        let ages: Vec<String> = view
+            .feeds
+            .iter()
+            .filter_map(|feed| Some(format!("{}: {}", feed.title(), freshness(feed)?)))
+            .collect();
+        let title = match ages.is_empty() {
+            true => title,
+            false => format!("{} ({})", title, ages.join("; ")),
+        };
+        let failures: usize = view.feeds.iter().map(|feed| feed.failing()).sum();
+        let title = format!("{}{}", title, failing(failures));
+        let since = view.feeds.iter().filter_map(|feed| feed.alert).max();
+        let stale = view.feeds.iter().any(|feed| feed.is_stale());
+        let alert = stale || failures > 0;
+        let border = border(false, alert, flash(self.alert, since));
+        let cursor = &mut view.cursor;
+        render_table(f, area, title, &scroll, grid, border, self.freeze, cursor);
+    }
 
-            f.render_stateful_widget(t, layout[0], &mut self.table_state);
+    /// Popup with the full value of the selected cell.
+    fn detail(&mut self, f: &mut Frame) {
+        let Some((entry, feed)) = self.selected_cell() else {
+            return;
+        };
+        let entity = entry.entity;
+        let pointer = match entity.pointer.as_str() {
+            "" => "-",
+            pointer => pointer,
+        };
+        // Change times are only kept for the latest result
+        let changed = match (entry.sample, feed.last_changed(&entity.pointer)) {
+            (0, Some(at)) => (
+                "Changed: ",
+                format!("{} ago", format::duration(at.elapsed())),
+            ),
+            (0, None) => ("Changed: ", "not seen".to_string()),
+            (sample, _) => ("Result: ", format!("-{} in history", sample)),
+        };
+        let label = Style::default().fg(Color::Green);
+        let mut text = vec![
+            Line::from(vec![Span::styled("Pointer: ", label), Span::raw(pointer)]),
+            Line::from(vec![
+                Span::styled("Type: ", label),
+                Span::raw(entity.value.type_name()),
+            ]),
+            Line::from(vec![Span::styled(changed.0, label), Span::raw(changed.1)]),
+        ];
+        if let Some(formatter) = Formatter::find(&feed.formats, &entity.pointer) {
+            text.push(Line::from(vec![
+                Span::styled("Shown as: ", label),
+                Span::raw(format!(
+                    "{} ({})",
+                    formatter.apply(&entity.value),
+                    formatter
+                )),
+            ]));
+        }
+        text.push(Line::from(""));
+        text.push(Line::from(entity.value.to_string()));
+
+        let area = popup(f.size(), 70, 50);
+        let title = format!("{} ({}: close)", entity.title, self.key(Action::Back));
+        f.render_widget(Clear, area);
+        f.render_widget(
+            Paragraph::new(text)
+                .wrap(Wrap { trim: false })
+                .block(Block::default().borders(Borders::ALL).title(title)),
+            area,
+        );
+    }
+}
+
+/// Rows moved by `PageUp` and `PageDown`.
+const PAGE: isize = 10;
+
+#[allow(clippy::too_many_arguments)]
+fn render_table(
+    f: &mut Frame,
+    area: Rect,
+    title: String,
+    scroll: &str,
+    grid: Grid,
+    border: Style,
+    freeze: bool,
+    cursor: &mut Cursor,
+) {
+    cursor.clamp(grid.rows.len(), grid.header.len());
+    let widths = grid.widths();
+    // Borders and the highlight symbol
+    let visible = cursor.visible(&widths, area.width.saturating_sub(4), freeze);
+    let scrolled = &visible[usize::from(freeze && visible.len() > 1)..];
+    let title = match visible.len() < widths.len() {
+        true => format!(
+            "{} (columns {}-{} of {}, {})",
+            title,
+            scrolled.first().map_or(0, |c| c + 1),
+            visible.last().map_or(0, |c| c + 1),
+            widths.len(),
+            scroll
+        ),
+        false => title,
+    };
+    let constraints: Vec<Constraint> = visible
+        .iter()
+        .map(|&column| Constraint::Length(widths[column]))
+        .collect();
+
+    let header_cells: Vec<Cell> = visible
+        .iter()
+        .map(|&column| {
+            let arrow = match &grid.sort {
+                Some(Sort { key, descending }) if key == &grid.keys[column] => match descending {
+                    true => " ▼",
+                    false => " ▲",
+                },
+                _ => "",
+            };
+            Cell::from(format!("{}{}", grid.header[column].to_uppercase(), arrow))
+                .style(Style::default().fg(Color::Green))
+        })
+        .collect();
+
+    let selected = cursor.selected();
+    let rows: Vec<Row> =
+        grid.rows
+            .iter()
+            .enumerate()
+            .map(|(i, cells)| {
+                Row::new(visible.iter().map(|&column| {
+                    let text = cells[column]
+                        .as_ref()
+                        .map(|e| grid.text(e))
+                        .unwrap_or_default();
+                    match selected == Some(i) && column == cursor.column {
+                        true => Cell::from(text)
+                            .style(Style::default().add_modifier(Modifier::REVERSED)),
+                        false => Cell::from(text),
+                    }
+                }))
+            })
+            .collect();
+
+    let t = Table::new(rows)
+        .header(Row::new(header_cells))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(border)
+                .title(title),
+        )
+        .highlight_symbol("# ")
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+        .widths(&constraints);
 
-            Ok(())
+    f.render_stateful_widget(t, area, &mut cursor.state);
+}
+
+/// Bold when focused, red when the data is stale or an assertion fails
+/// and inverted while flashing.
+fn border(focused: bool, alert: bool, flash: bool) -> Style {
+    let style = match focused {
+        true => Style::default().add_modifier(Modifier::BOLD),
+        false => Style::default(),
+    };
+    match (alert, flash) {
+        (_, true) => style.fg(Color::Black).bg(Color::Red),
+        (true, false) => style.fg(Color::Red),
+        (false, false) => style,
+    }
+}
+
+/// How old the document is by its own timestamp, for table titles.
+fn age(feed: &Feed) -> String {
+    match freshness(feed) {
+        Some(freshness) => format!(" ({})", freshness),
+        None => String::new(),
+    }
+}
+
+/// How long ago the document was produced, for feeds with a timestamp.
+fn freshness(feed: &Feed) -> Option<String> {
+    let freshness = match (feed.timestamp.as_ref()?, feed.age()) {
+        (_, Some(age)) if feed.is_stale() => {
+            format!("stale, updated {} ago", format::duration(age))
         }
+        (_, Some(age)) => format!("updated {} ago", format::duration(age)),
+        (pointer, None) => format!("no timestamp at {}", pointer),
+    };
+    Some(freshness)
+}
+
+/// Title suffix counting the failing assertions.
+fn failing(count: usize) -> String {
+    match count {
+        0 => String::new(),
+        1 => " (1 assertion failing)".to_string(),
+        n => format!(" ({} assertions failing)", n),
+    }
+}
+
+/// Whether a border alerting since `since` is lit right now, it blinks
+/// twice a second.
+fn flash(style: AlertStyle, since: Option<Instant>) -> bool {
+    style != AlertStyle::Bell
+        && since.is_some_and(|at| at.elapsed().as_millis() / 500 % 2 == 0)
+}
+
+/// A centered area taking the given percentages of `area`.
+fn popup(area: Rect, width: u16, height: u16) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![
+            Constraint::Percentage((100 - height) / 2),
+            Constraint::Percentage(height),
+            Constraint::Percentage((100 - height) / 2),
+        ])
+        .split(area);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![
+            Constraint::Percentage((100 - width) / 2),
+            Constraint::Percentage(width),
+            Constraint::Percentage((100 - width) / 2),
+        ])
+        .split(vertical[1])[1]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Message {
+    SelectView(usize),
+    ToggleRaw,
+    ScrollRaw(isize),
+    ScrollRawTo(usize),
+    NextMatch(isize),
+    OpenPrompt(PromptKind),
+    PromptKey(KeyEvent),
+    Focus(usize),
+    OpenExplorer,
+    CloseExplorer,
+    OpenDiff,
+    CloseDiff,
+    DiffUp,
+    DiffDown,
+    ExplorerUp,
+    ExplorerDown,
+    ExplorerExpand,
+    ExplorerCollapse,
+    ExplorerToggle,
+    TogglePointer,
+    MoveRow(isize),
+    MoveRowTo(usize),
+    MoveColumn(isize),
+    OpenDetail,
+    CloseDetail,
+    Transpose,
+    OpenHelp,
+    CloseHelp,
+    Refresh,
+    Pause,
+    Acknowledge,
+    Sort,
+    ClearSort,
+    Quit,
+    Tick,
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use std::time::Duration;
+
+    use clap::{CommandFactory, FromArgMatches};
+    use serde_json::json;
+
+    use crate::{
+        args::{AlertStyle, SourceLayout, TJsonArgs},
+        keymap::Keymap,
+        ui::{App, Message, Mode},
+    };
+
+    fn app(argv: &[&str]) -> App {
+        let matches = TJsonArgs::command().get_matches_from(argv);
+        let args = TJsonArgs::from_arg_matches(&matches).unwrap();
+        App::new(
+            args.views(&matches).unwrap(),
+            SourceLayout::Stacked,
+            false,
+            false,
+            AlertStyle::Flash,
+            Keymap::default(),
+        )
     }
 
-    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-    enum Message {
-        UpdateSource,
-        Quit,
-        Tick,
+    #[tokio::test]
+    async fn refresh_while_paused_is_shown() {
+        let mut app = app(&["tjson", "-p", "/a", "--exec", "echo '{\"a\": 1}'"]);
+
+        app.update(Message::Pause).unwrap();
+        app.update(Message::Refresh).unwrap();
+        for _ in 0..50 {
+            if app.views[0].feeds[0].document.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            app.update(Message::Tick).unwrap();
+        }
+        assert!(app.paused);
+        assert_eq!(app.views[0].feeds[0].columns.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn arrays_are_not_toggled() {
+        let mut app = app(&["tjson", "--calc", "n=/a", "--exec", "true"]);
+        assert!(matches!(app.mode, Mode::Table));
+        app.views[0].feeds[0].document = Some(json!({"a": [1, 2]}));
+
+        app.update(Message::OpenExplorer).unwrap();
+        app.update(Message::TogglePointer).unwrap();
+
+        assert!(app.views[0].feeds[0].pointers.is_empty());
+        assert_eq!(
+            app.notice.as_deref(),
+            Some("/a is an array, pick its items")
+        );
     }
 }
@@ -0,0 +1,26 @@
+use crate::{args::ViewArgs, feed::Feed, table::Cursor};
+
+/// A named set of feeds shown together in one tab.
+pub struct View {
+    pub name: String,
+    pub feeds: Vec<Feed>,
+    /// The feed that keys like the explorer act on.
+    pub focus: usize,
+    /// Selection of the merged table.
+    pub cursor: Cursor,
+}
+
+impl View {
+    pub fn new(args: ViewArgs) -> Self {
+        Self {
+            name: args.name,
+            feeds: args.feeds.into_iter().map(Feed::new).collect(),
+            focus: 0,
+            cursor: Cursor::default(),
+        }
+    }
+
+    pub fn focused(&mut self) -> &mut Feed {
+        &mut self.feeds[self.focus]
+    }
+}
@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use eyre::eyre;
+use eyre::Result;
+use futures::{SinkExt, StreamExt};
+use serde_json::Value;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+use crate::source::STREAM_BUFFER;
+
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Connects to a websocket, optionally sends a subscription message and
+/// sends the json in every text frame. Reconnects with exponential backoff
+/// and stops when the receiver is dropped.
+pub fn subscribe(url: &str, message: Option<&str>) -> Receiver<Result<Value>> {
+    let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+    let url = url.to_string();
+    let message = message.map(str::to_string);
+    tokio::spawn(async move {
+        let mut backoff = MIN_BACKOFF;
+        loop {
+            if let Err(e) = listen(&url, message.as_deref(), &mut backoff, &tx).await {
+                if tx.send(Err(e)).await.is_err() {
+                    return;
+                }
+            }
+            if tx.is_closed() {
+                return;
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    });
+    rx
+}
+
+async fn listen(
+    url: &str,
+    message: Option<&str>,
+    backoff: &mut Duration,
+    tx: &Sender<Result<Value>>,
+) -> Result<()> {
+    let (mut socket, _) = connect_async(url).await?;
+    *backoff = MIN_BACKOFF;
+
+    if let Some(message) = message {
+        socket.send(Message::text(message)).await?;
+    }
+
+    while let Some(frame) = socket.next().await {
+        let value = match frame? {
+            Message::Text(text) => {
+                serde_json::from_str(&text).map_err(|e| eyre!("Could not parse message: {}", e))
+            }
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        if tx.send(value).await.is_err() {
+            return Ok(());
+        }
+    }
+    Err(eyre!("Connection closed, reconnecting"))
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use serde_json::json;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::accept_async;
+
+    use crate::ws::subscribe;
+
+    #[tokio::test]
+    async fn echo_subscription() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let socket = accept_async(stream).await.unwrap();
+            let (write, read) = socket.split();
+            read.forward(write).await.unwrap();
+        });
+
+        let mut rx = subscribe(&format!("ws://{}", addr), Some("{\"status\": \"up\"}"));
+
+        assert_eq!(rx.recv().await.unwrap().unwrap(), json!({"status": "up"}));
+    }
+}